@@ -0,0 +1,196 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    domain::core::{LyricsWithChords, Options},
+    export::fetch_song,
+    network::Network,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BatchEvent {
+    Fetched {
+        index: usize,
+        url: String,
+        finished: usize,
+        total: usize,
+    },
+    Failed {
+        index: usize,
+        url: String,
+        error: String,
+        finished: usize,
+        total: usize,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchError {
+    pub index: usize,
+    pub url: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResult {
+    // in the same order as the urls, without the failed ones
+    pub songs: Vec<LyricsWithChords>,
+    pub errors: Vec<BatchError>,
+    pub cancelled: bool,
+}
+
+// the running batches, so they can be cancelled from another command
+#[derive(Default)]
+pub struct FetchBatches {
+    batches: Mutex<HashMap<String, (u64, CancellationToken)>>,
+    started: AtomicU64,
+}
+
+impl FetchBatches {
+    fn start(&self, batch_id: &str) -> (u64, CancellationToken) {
+        let token = CancellationToken::new();
+        let generation = self.started.fetch_add(1, Ordering::Relaxed);
+
+        // a batch started again with the same id replaces the old one
+        if let Some((_, previous)) = self
+            .batches
+            .lock()
+            .unwrap()
+            .insert(batch_id.to_string(), (generation, token.clone()))
+        {
+            previous.cancel();
+        }
+
+        (generation, token)
+    }
+
+    pub fn cancel(&self, batch_id: &str) -> bool {
+        match self.batches.lock().unwrap().remove(batch_id) {
+            Some((_, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn finish(&self, batch_id: &str, generation: u64) {
+        let mut batches = self.batches.lock().unwrap();
+
+        if matches!(batches.get(batch_id), Some((g, _)) if *g == generation) {
+            batches.remove(batch_id);
+        }
+    }
+}
+
+// the network limits how many requests go to one site at once,
+// so all the songs can be started together
+pub async fn fetch_many(
+    network: &Network,
+    batches: &FetchBatches,
+    batch_id: &str,
+    urls: Vec<String>,
+    options: Option<Options>,
+    on_event: impl Fn(BatchEvent),
+) -> BatchResult {
+    let (generation, token) = batches.start(batch_id);
+    let total = urls.len();
+
+    // a panicking task does not return its index,
+    // the songs left here when all the tasks are joined have crashed
+    let mut spawned = HashMap::new();
+
+    let mut tasks = JoinSet::new();
+    for (index, url) in urls.into_iter().enumerate() {
+        let network = network.clone();
+        let options = options.clone();
+
+        spawned.insert(index, url.clone());
+        tasks.spawn(async move { (index, fetch_song(&network, url, options).await) });
+    }
+
+    let mut songs = Vec::new();
+    let mut errors = Vec::new();
+    let mut record = |index: usize, url: String, result: anyhow::Result<LyricsWithChords>| {
+        let finished = songs.len() + errors.len() + 1;
+        match result {
+            Ok(song) => {
+                on_event(BatchEvent::Fetched {
+                    index,
+                    url,
+                    finished,
+                    total,
+                });
+                songs.push((index, song));
+            }
+            Err(e) => {
+                on_event(BatchEvent::Failed {
+                    index,
+                    url: url.clone(),
+                    error: e.to_string(),
+                    finished,
+                    total,
+                });
+                errors.push(BatchError {
+                    index,
+                    url,
+                    error: e.to_string(),
+                });
+            }
+        }
+    };
+
+    let mut cancelled = false;
+    loop {
+        let next = tokio::select! {
+            _ = token.cancelled() => {
+                tasks.abort_all();
+                cancelled = true;
+                break;
+            }
+            next = tasks.join_next() => next,
+        };
+
+        let Some(joined) = next else {
+            break;
+        };
+
+        // the tasks are only aborted when the batch is cancelled,
+        // so joining fails when the task panicked
+        let Ok((index, result)) = joined else {
+            continue;
+        };
+        if let Some(url) = spawned.remove(&index) {
+            record(index, url, result);
+        }
+    }
+
+    if !cancelled {
+        let mut crashed: Vec<(usize, String)> = spawned.into_iter().collect();
+        crashed.sort();
+
+        for (index, url) in crashed {
+            record(index, url, Err(anyhow::Error::msg("The download crashed!")));
+        }
+    }
+
+    batches.finish(batch_id, generation);
+
+    songs.sort_by_key(|(index, _)| *index);
+    errors.sort_by_key(|e| e.index);
+
+    BatchResult {
+        songs: songs.into_iter().map(|(_, song)| song).collect(),
+        errors,
+        cancelled,
+    }
+}
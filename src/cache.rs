@@ -0,0 +1,68 @@
+use std::{io, path::PathBuf, time::Duration};
+
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+// fetched pages are stored as files named by the hash of their url,
+// the age of an entry is the modification time of its file
+#[derive(Clone, Debug)]
+pub struct PageCache {
+    directory: PathBuf,
+    ttl: Duration,
+}
+
+pub enum CachedPage {
+    Fresh(String),
+    Stale(String),
+    Missing,
+}
+
+impl PageCache {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+    pub fn new(directory: PathBuf, ttl: Duration) -> Self {
+        Self { directory, ttl }
+    }
+
+    pub fn in_app_data_dir(app: &tauri::AppHandle, ttl: Duration) -> Option<Self> {
+        let directory = app.path_resolver().app_data_dir()?.join("page-cache");
+
+        Some(Self::new(directory, ttl))
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.directory
+            .join(format!("{:x}", Sha256::digest(url.as_bytes())))
+    }
+
+    pub async fn get(&self, url: &str) -> CachedPage {
+        let path = self.path(url);
+
+        let Ok(content) = fs::read_to_string(&path).await else {
+            return CachedPage::Missing;
+        };
+
+        let age = fs::metadata(&path)
+            .await
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+
+        match age {
+            Some(age) if age <= self.ttl => CachedPage::Fresh(content),
+            _ => CachedPage::Stale(content),
+        }
+    }
+
+    pub async fn put(&self, url: &str, content: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.directory).await?;
+        fs::write(self.path(url), content).await
+    }
+
+    pub async fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.directory).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::core::TextNode;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AlignmentGuess {
+    // the chord was further right than the end of the lyrics,
+    // so it was appended to the end of the line
+    Overflow { chord: String, column: usize },
+    // the chord pointed into the middle of a wide character,
+    // so it was moved to the start of that character
+    Snapped { chord: String, column: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment {
+    pub line: Vec<TextNode>,
+    pub guesses: Vec<AlignmentGuess>,
+}
+
+// a line that contains chords and nothing but whitespace,
+// bar lines and repeat marks in between them
+pub fn is_chord_line(line: &[TextNode]) -> bool {
+    line.iter().any(|n| matches!(n, TextNode::Chord(_)))
+        && line.iter().all(|n| match n {
+            TextNode::Chord(_) => true,
+            TextNode::Text(t) => t.split_whitespace().all(is_filler),
+            _ => false,
+        })
+}
+
+// bar lines, repeats and "no chord" marks found between the chords
+pub fn is_filler(token: &str) -> bool {
+    let token = token.to_lowercase();
+
+    token.chars().all(|c| "|/-%.:".contains(c))
+        || ["n.c.", "nc", "n.c"].contains(&token.as_str())
+        || token
+            .trim_start_matches("(")
+            .trim_end_matches(")")
+            .trim_start_matches("x")
+            .trim_end_matches("x")
+            .parse::<u32>()
+            .is_ok()
+}
+
+// the columns at which the chords of a chord line start,
+// the chord names themselves take up space in the line too
+pub fn chord_columns(chord_line: &[TextNode]) -> Vec<(usize, String)> {
+    let mut column = 0;
+    let mut chords = Vec::new();
+
+    for node in chord_line {
+        match node {
+            TextNode::Text(t) => column += t.width(),
+            TextNode::Chord(ch) => {
+                chords.push((column, ch.clone()));
+                column += ch.width();
+            }
+            _ => {}
+        }
+    }
+
+    chords
+}
+
+// puts the chords into the lyrics at exactly the columns they were written at
+pub fn merge_chord_line(chords: &[(usize, String)], lyrics: &str) -> Alignment {
+    let mut chords = chords.to_vec();
+    chords.sort_by_key(|(column, _)| *column);
+
+    let mut line = Vec::new();
+    let mut guesses = Vec::new();
+
+    let mut chords = chords.into_iter().peekable();
+    let mut current_text = String::new();
+    let mut column = 0;
+
+    for c in lyrics.chars() {
+        let width = c.width().unwrap_or(0);
+
+        // combining characters belong to the character before them
+        if width == 0 {
+            current_text.push(c);
+            continue;
+        }
+
+        while let Some((chord_column, _)) = chords.peek() {
+            if *chord_column > column {
+                break;
+            }
+
+            let (_, chord) = chords.next().unwrap();
+            push_chord(&mut line, &mut current_text, chord);
+        }
+
+        // a chord pointing into the middle of a wide character
+        // is put in front of it
+        while let Some((chord_column, _)) = chords.peek() {
+            if *chord_column >= column + width {
+                break;
+            }
+
+            let (chord_column, chord) = chords.next().unwrap();
+            guesses.push(AlignmentGuess::Snapped {
+                chord: chord.clone(),
+                column: chord_column,
+            });
+
+            push_chord(&mut line, &mut current_text, chord);
+        }
+
+        current_text.push(c);
+        column += width;
+    }
+
+    for (chord_column, chord) in chords {
+        if chord_column > column {
+            guesses.push(AlignmentGuess::Overflow {
+                chord: chord.clone(),
+                column: chord_column,
+            });
+
+            // keep the overflowing chords apart from the last word
+            if current_text != "" && !current_text.ends_with(" ") {
+                current_text.push(' ');
+            }
+        }
+
+        push_chord(&mut line, &mut current_text, chord);
+    }
+
+    if current_text != "" {
+        line.push(TextNode::Text(current_text));
+    }
+
+    Alignment { line, guesses }
+}
+
+fn push_chord(line: &mut Vec<TextNode>, current_text: &mut String, chord: String) {
+    if current_text != "" {
+        line.push(TextNode::Text(current_text.clone()));
+        current_text.clear();
+    } else if matches!(line.last(), Some(TextNode::Chord(_))) {
+        // we keep streaks of chords apart
+        line.push(TextNode::Text(" ".to_string()));
+    }
+
+    line.push(TextNode::Chord(chord));
+}
@@ -0,0 +1,384 @@
+use itertools::Itertools;
+use nom::{
+    branch::alt,
+    bytes::complete::take_while1,
+    character::complete::char,
+    combinator::map,
+    error::{context, ContextError, ErrorKind, ParseError},
+    sequence::delimited,
+    IResult,
+};
+
+use super::core::{LyricsWithChords, Options, SectionCounter, SectionKind, SongMetadata, TextNode};
+
+pub struct ChordPro;
+
+impl ChordPro {
+    pub const EXTENSIONS: [&'static str; 4] = ["cho", "chopro", "chordpro", "crd"];
+
+    // one file can hold more songs separated by {new_song}
+    pub fn parse(input: &str, options: Option<Options>) -> anyhow::Result<Vec<LyricsWithChords>> {
+        let user_options = options.unwrap_or_default();
+
+        let mut songs = Vec::new();
+        let mut song = SongBuilder::default();
+        let mut in_tab = false;
+
+        for line in input.replace("\r\n", "\n").split("\n") {
+            let trimmed_line = line.trim();
+
+            if in_tab {
+                if matches!(directive(trimmed_line), Some((name, _)) if name == "end_of_tab" || name == "eot")
+                {
+                    in_tab = false;
+                    song.end_section();
+                } else {
+                    song.lines.push(match line {
+                        "" => vec![],
+                        _ => vec![TextNode::Text(line.to_string())],
+                    });
+                }
+
+                continue;
+            }
+
+            if trimmed_line.starts_with("#") {
+                continue;
+            }
+
+            let Some((name, value)) = directive(trimmed_line) else {
+                let parsed_line = parse_line(line)?;
+
+                // the blank line after {eoc} is already there
+                if parsed_line.len() == 0 && matches!(song.lines.last(), Some(l) if l.len() == 0) {
+                    continue;
+                }
+
+                song.lines.push(parsed_line);
+                continue;
+            };
+
+            let value = value.map(|v| v.to_string());
+            match name.as_str() {
+                "title" | "t" => song.song_name = value,
+                "artist" => song.artist = value,
+                "subtitle" | "st" if song.artist.is_none() => song.artist = value,
+                "composer" => song.metadata.composer = value,
+                "lyricist" => song.metadata.lyricist = value,
+                "album" => song.metadata.album = value,
+                "key" => song.metadata.key = value,
+                "capo" => song.metadata.capo = value.and_then(|v| v.parse().ok()),
+                "comment" | "c" | "comment_italic" | "ci" | "comment_box" | "cb" => {
+                    if let Some(comment) = value {
+                        song.lines.push(vec![TextNode::Label(comment)]);
+                    }
+                }
+                "start_of_chorus" | "soc" => {
+                    song.start_section(&user_options, SectionKind::Chorus, value)
+                }
+                "start_of_verse" | "sov" => {
+                    song.start_section(&user_options, SectionKind::Verse, value)
+                }
+                "start_of_bridge" | "sob" => {
+                    song.start_section(&user_options, SectionKind::Bridge, value)
+                }
+                "start_of_tab" | "sot" => {
+                    in_tab = true;
+                    song.start_section(&user_options, SectionKind::Other, value);
+                }
+                "end_of_chorus" | "eoc" | "end_of_verse" | "eov" | "end_of_bridge" | "eob" => {
+                    song.end_section()
+                }
+                // a reference to the chorus, it is not written out again
+                "chorus" => {
+                    song.start_section(&user_options, SectionKind::Chorus, value);
+                    song.end_section();
+                }
+                "new_song" | "ns" => {
+                    if !song.is_empty() {
+                        songs.push(song.build());
+                    }
+
+                    song = SongBuilder::default();
+                }
+                _ => {}
+            }
+        }
+
+        if !song.is_empty() || songs.len() == 0 {
+            songs.push(song.build());
+        }
+
+        Ok(songs)
+    }
+
+    pub fn render(song: &LyricsWithChords, options: Option<Options>) -> String {
+        let user_options = options.unwrap_or_default();
+
+        let mut directives = vec![
+            ("title", Some(song.song_name.clone())),
+            ("artist", Some(song.artist.clone())),
+            ("composer", song.metadata.composer.clone()),
+            ("lyricist", song.metadata.lyricist.clone()),
+            ("album", song.metadata.album.clone()),
+            ("key", song.metadata.key.clone()),
+            ("capo", song.metadata.capo.map(|c| c.to_string())),
+        ];
+        directives.retain(|(_, value)| matches!(value, Some(v) if v != ""));
+
+        let mut output: Vec<String> = directives
+            .iter()
+            .map(|(name, value)| format!("{{{}: {}}}", name, value.clone().unwrap()))
+            .collect();
+        output.push("".to_string());
+
+        // the chorus lasts until the next blank line or label
+        let mut chorus_lines: Option<usize> = None;
+        let mut in_tab = false;
+        let close_chorus = |output: &mut Vec<String>, chorus_lines: &mut Option<usize>| {
+            match chorus_lines.take() {
+                // a chorus label without any lines repeats the chorus
+                Some(0) => {
+                    let start = output.pop().unwrap_or_default();
+                    output.push(start.replace("{soc", "{chorus"));
+                }
+                Some(_) => output.push("{eoc}".to_string()),
+                None => {}
+            }
+        };
+
+        for line in song.text.split(|n| n == &TextNode::Newline) {
+            let mut line = line.to_vec();
+            let has_label = matches!(line.first(), Some(TextNode::Label(_)));
+
+            if let Some(TextNode::Label(label)) = line.first().cloned() {
+                if in_tab {
+                    output.push("{eot}".to_string());
+                    in_tab = false;
+                }
+                close_chorus(&mut output, &mut chorus_lines);
+
+                if label == user_options.chorus_label {
+                    output.push("{soc}".to_string());
+                    chorus_lines = Some(0);
+                } else if SectionKind::from_label(&label).0 == SectionKind::Chorus {
+                    output.push(format!("{{soc: {}}}", label));
+                    chorus_lines = Some(0);
+                } else {
+                    output.push(format!("{{c: {}}}", label));
+                }
+
+                line.remove(0);
+                if let Some(TextNode::Text(t)) = line.first_mut() {
+                    *t = t.trim_start().to_string();
+                }
+            }
+
+            let rendered_line = line
+                .iter()
+                .map(|n| match n {
+                    TextNode::Text(t) => t.clone(),
+                    TextNode::Chord(ch) => format!("[{}]", ch),
+                    TextNode::Label(l) => l.clone(),
+                    TextNode::Newline => unreachable!(),
+                })
+                .collect::<String>();
+
+            // brackets which are not chords, or a line looking like a comment
+            // or a directive would be read differently, a tab is kept as it is
+            let is_literal = !line.iter().any(|n| matches!(n, TextNode::Chord(_)))
+                && (rendered_line.contains(['[', ']'])
+                    || rendered_line.trim_start().starts_with("#")
+                    || directive(rendered_line.trim()).is_some());
+
+            if in_tab && (!is_literal || rendered_line.trim() == "") {
+                output.push("{eot}".to_string());
+                in_tab = false;
+            }
+
+            if rendered_line.trim() == "" {
+                if has_label && line.len() == 0 {
+                    // the label was the whole line
+                    continue;
+                }
+
+                close_chorus(&mut output, &mut chorus_lines);
+                output.push("".to_string());
+                continue;
+            }
+
+            if let Some(lines) = chorus_lines.as_mut() {
+                *lines += 1;
+            }
+
+            if is_literal && !in_tab {
+                output.push("{sot}".to_string());
+                in_tab = true;
+            }
+
+            output.push(rendered_line);
+        }
+
+        if in_tab {
+            output.push("{eot}".to_string());
+        }
+        close_chorus(&mut output, &mut chorus_lines);
+
+        output.join("\n") + "\n"
+    }
+
+    pub fn render_songbook(songs: &[LyricsWithChords], options: Option<Options>) -> String {
+        songs
+            .iter()
+            .map(|song| Self::render(song, options.clone()))
+            .join("\n{new_song}\n")
+    }
+}
+
+#[derive(Default)]
+struct SongBuilder {
+    metadata: SongMetadata,
+    artist: Option<String>,
+    song_name: Option<String>,
+    lines: Vec<Vec<TextNode>>,
+    section_counter: SectionCounter,
+}
+
+impl SongBuilder {
+    fn start_section(&mut self, options: &Options, kind: SectionKind, label: Option<String>) {
+        if matches!(self.lines.last(), Some(l) if l.len() != 0) {
+            self.lines.push(vec![]);
+        }
+
+        // {soc: Refrain} names the section itself
+        let label = label.or_else(|| self.section_counter.next(options, kind, None));
+
+        if let Some(label) = label {
+            self.lines.push(vec![TextNode::Label(label)]);
+        }
+    }
+
+    fn end_section(&mut self) {
+        self.lines.push(vec![]);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.song_name.is_none() && self.lines.iter().all(|l| l.len() == 0)
+    }
+
+    fn build(mut self) -> LyricsWithChords {
+        // blank lines at the start and the end come from the directives
+        while matches!(self.lines.first(), Some(l) if l.len() == 0) {
+            self.lines.remove(0);
+        }
+
+        while matches!(self.lines.last(), Some(l) if l.len() == 0) {
+            self.lines.pop();
+        }
+
+        let mut lyrics = LyricsWithChords::new(
+            self.lines.join(&TextNode::Newline),
+            self.artist.unwrap_or_default(),
+            self.song_name.unwrap_or_default(),
+        );
+        lyrics.metadata = self.metadata;
+
+        lyrics
+    }
+}
+
+// "{title: Song}" -> ("title", Some("Song")), "{soc}" -> ("soc", None)
+fn directive(line: &str) -> Option<(String, Option<&str>)> {
+    let inner = line.strip_prefix("{")?.strip_suffix("}")?;
+
+    let (name, value) = match inner
+        .char_indices()
+        .find(|(_, c)| *c == ':' || c.is_whitespace())
+    {
+        Some((i, c)) => {
+            let value = inner[i + c.len_utf8()..].trim_start();
+
+            // "{title : Song}"
+            let value = match c {
+                ':' => value,
+                _ => value.strip_prefix(':').unwrap_or(value),
+            };
+
+            (&inner[..i], Some(value.trim()))
+        }
+        None => (inner, None),
+    };
+
+    Some((name.trim().to_lowercase(), value.filter(|v| *v != "")))
+}
+
+// inline chords in brackets, "[G]Hello [C]world"
+pub(crate) fn parse_line(line: &str) -> anyhow::Result<Vec<TextNode>> {
+    match parse_lyrics_with_chords::<(&str, ErrorKind)>(line) {
+        Ok(r) => Ok(r),
+        Err((e, kind)) => Err(anyhow::Error::msg(format!("{}: {}", kind.description(), e))),
+    }
+}
+
+fn string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    let chars = "\n[]";
+
+    take_while1(move |c| !chars.contains(c))(i)
+}
+
+fn chord<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, TextNode, E> {
+    context(
+        "chord",
+        map(delimited(char('['), string, char(']')), |o| {
+            TextNode::Chord(o.to_string())
+        }),
+    )(i)
+}
+
+fn text<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, TextNode, E> {
+    context("text", map(string, |o| TextNode::Text(o.to_string())))(i)
+}
+
+// a stray bracket is kept as text
+fn bracket<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, TextNode, E> {
+    map(alt((char('['), char(']'))), |c| {
+        TextNode::Text(c.to_string())
+    })(i)
+}
+
+fn parse_lyrics_with_chords<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> Result<Vec<TextNode>, E> {
+    let mut tag_parser = alt((chord::<'a, E>, text::<'a, E>, bracket::<'a, E>));
+
+    let mut tags = Vec::new();
+    let mut s = i;
+    while s.len() != 0 {
+        let (rest, node) = match tag_parser(s) {
+            Ok(r) => r,
+            Err(e) => match e {
+                nom::Err::Incomplete(_) => {
+                    return Err(E::from_error_kind(
+                        "Need more data",
+                        nom::error::ErrorKind::Eof,
+                    ))
+                }
+                nom::Err::Failure(err) | nom::Err::Error(err) => return Err(err),
+            },
+        };
+
+        tags.push(node);
+
+        s = rest;
+    }
+
+    Ok(tags)
+}
@@ -1,52 +1,409 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use docx::{
     document::{Paragraph, Run, Text, TextSpace},
-    formatting::{CharacterProperty, VerticalAlignment},
+    formatting::{CharacterProperty, ParagraphProperty, VerticalAlignment},
     Docx,
 };
 use scraper::Html;
 use serde::{Deserialize, Serialize};
 
+use super::alignment::is_chord_line;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LyricsWithChords {
     pub text: Vec<TextNode>,
     pub artist: String,
     pub song_name: String,
+    #[serde(default)]
+    pub metadata: SongMetadata,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SongMetadata {
+    pub lyricist: Option<String>,
+    pub composer: Option<String>,
+    pub album: Option<String>,
+    pub submitter: Option<String>,
+    pub key: Option<String>,
+    pub capo: Option<u32>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Options {
     pub chorus_label: String,
+    // maps section kinds to the label put in the output,
+    // `None` drops the label, "{n}" is replaced by the section number,
+    // the kinds without a mapping keep the label written in the song
+    #[serde(default)]
+    pub section_labels: HashMap<SectionKind, Option<String>>,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
             chorus_label: "®:".to_string(),
+            section_labels: HashMap::new(),
         }
     }
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    // only the lyrics, for the singers and the projector
+    pub lyrics_only: bool,
+}
+
+impl Options {
+    pub fn section_label(&self, kind: SectionKind, number: usize) -> Option<String> {
+        let template = match self.section_labels.get(&kind) {
+            Some(t) => t.clone(),
+            None if kind == SectionKind::Chorus => Some(self.chorus_label.clone()),
+            None => None,
+        };
+
+        template.map(|t| t.replace("{n}", &number.to_string()))
+    }
+
+    fn maps(&self, kind: SectionKind) -> bool {
+        kind == SectionKind::Chorus || self.section_labels.contains_key(&kind)
+    }
+
+    // the reverse of `section_label`, used when reading our own labels back
+    pub fn section_kind(&self, label: &str) -> (SectionKind, Option<usize>) {
+        let label = label.trim();
+
+        if label == self.chorus_label.trim() {
+            return (SectionKind::Chorus, None);
+        }
+
+        for (kind, template) in self.section_labels.iter() {
+            let Some(template) = template else {
+                continue;
+            };
+
+            let number = match template.split_once("{n}") {
+                Some((prefix, suffix)) => label
+                    .strip_prefix(prefix)
+                    .and_then(|l| l.strip_suffix(suffix))
+                    .and_then(|n| n.parse().ok()),
+                None if label == template => return (*kind, None),
+                None => None,
+            };
+
+            if number.is_some() {
+                return (*kind, number);
+            }
+        }
+
+        match SectionKind::from_label(label) {
+            // "1.", "2)" are verses
+            (SectionKind::Other, Some(number))
+                if label.starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                (SectionKind::Verse, Some(number))
+            }
+            k => k,
+        }
+    }
+}
+
+// a part of a song separated by blank lines or started by a label
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    pub kind: SectionKind,
+    pub number: Option<usize>,
+    pub label: Option<String>,
+    pub lines: Vec<Vec<TextNode>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SectionKind {
+    Verse,
+    Chorus,
+    PreChorus,
+    PostChorus,
+    Bridge,
+    Intro,
+    Outro,
+    Interlude,
+    Instrumental,
+    Solo,
+    Other,
+}
+
+impl SectionKind {
+    // "Verse 2" -> (Verse, Some(2)), "Pre-Chorus" -> (PreChorus, None),
+    // the kind is named by the first word, "In the universe" is not a verse
+    pub fn from_label(label: &str) -> (Self, Option<usize>) {
+        let label = label.trim().to_lowercase();
+
+        let number = label
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|part| !part.is_empty())
+            .and_then(|part| part.parse().ok());
+
+        let kind = leading_kind(&label).map_or(SectionKind::Other, |(kind, _)| kind);
+
+        (kind, number)
+    }
+
+    // a label which only names the section, "Bridge", "Verse 2", "Pre chorus",
+    // and not "Verse of the night"
+    pub fn from_name(name: &str) -> Option<(Self, Option<usize>)> {
+        let name = name.trim().to_lowercase();
+        let (kind, rest) = leading_kind(&name)?;
+
+        match rest.trim() {
+            "" => Some((kind, None)),
+            number => number.parse().ok().map(|n| (kind, Some(n))),
+        }
+    }
+}
+
+// the kind named by the first word of a lowercase label and the rest of the label,
+// "verse2" -> (Verse, "2"), "chorus x2" -> (Chorus, " x2")
+fn leading_kind(label: &str) -> Option<(SectionKind, &str)> {
+    let names = [
+        ("pre-chorus", SectionKind::PreChorus),
+        ("pre chorus", SectionKind::PreChorus),
+        ("post-chorus", SectionKind::PostChorus),
+        ("post chorus", SectionKind::PostChorus),
+        ("chorus", SectionKind::Chorus),
+        ("refrain", SectionKind::Chorus),
+        ("refrén", SectionKind::Chorus),
+        ("refren", SectionKind::Chorus),
+        ("ref", SectionKind::Chorus),
+        ("verse", SectionKind::Verse),
+        ("sloka", SectionKind::Verse),
+        ("sloha", SectionKind::Verse),
+        ("bridge", SectionKind::Bridge),
+        ("intro", SectionKind::Intro),
+        ("predohra", SectionKind::Intro),
+        ("předehra", SectionKind::Intro),
+        ("outro", SectionKind::Outro),
+        ("coda", SectionKind::Outro),
+        ("dohra", SectionKind::Outro),
+        ("interlude", SectionKind::Interlude),
+        ("medzihra", SectionKind::Interlude),
+        ("mezihra", SectionKind::Interlude),
+        ("instrumental", SectionKind::Instrumental),
+        ("solo", SectionKind::Solo),
+    ];
+
+    names.into_iter().find_map(|(name, kind)| {
+        let rest = label.strip_prefix(name)?;
+
+        // a whole word, "verse" but not "versed"
+        (!rest.starts_with(char::is_alphabetic)).then_some((kind, rest))
+    })
+}
+
+// keeps track of how many sections of each kind we have seen,
+// so unnumbered verses still get "1.", "2.", ...
+#[derive(Default)]
+pub struct SectionCounter {
+    counts: HashMap<SectionKind, usize>,
+}
+
+impl SectionCounter {
+    pub fn label(&mut self, options: &Options, label: &str) -> Option<String> {
+        let (kind, number) = SectionKind::from_label(label);
+
+        self.relabel(options, kind, number, label)
+    }
+
+    // a label written in the song stays as it is
+    // unless the user has a label for its kind
+    pub fn relabel(
+        &mut self,
+        options: &Options,
+        kind: SectionKind,
+        number: Option<usize>,
+        written: &str,
+    ) -> Option<String> {
+        let label = self.next(options, kind, number);
+
+        if options.maps(kind) {
+            label
+        } else {
+            Some(written.trim().to_string())
+        }
+    }
+
+    pub fn next(
+        &mut self,
+        options: &Options,
+        kind: SectionKind,
+        number: Option<usize>,
+    ) -> Option<String> {
+        let count = self.counts.entry(kind).or_insert(0);
+        *count = number.unwrap_or(*count + 1);
+
+        options.section_label(kind, *count)
+    }
+}
+
+// the style of the "Artist - Title" paragraphs of the docx export,
+// the songs are told apart by it when the songbook is read back
+pub const TITLE_STYLE: &str = "Title";
+
 impl LyricsWithChords {
     pub fn new(text: Vec<TextNode>, artist: String, song_name: String) -> Self {
         Self {
             text,
             artist,
             song_name,
+            metadata: SongMetadata::default(),
+        }
+    }
+
+    pub fn sections(&self, options: &Options) -> Vec<Section> {
+        let mut sections = Vec::new();
+        let mut section: Option<Section> = None;
+
+        for line in self.text.split(|n| n == &TextNode::Newline) {
+            let mut line = line.to_vec();
+
+            let starts_with_label = matches!(line.first(), Some(TextNode::Label(_)));
+            if let Some(TextNode::Label(label)) = line.first().cloned() {
+                sections.extend(section.take());
+
+                let (kind, number) = options.section_kind(&label);
+                section = Some(Section {
+                    kind,
+                    number,
+                    label: Some(label),
+                    lines: Vec::new(),
+                });
+
+                line.remove(0);
+                if let Some(TextNode::Text(t)) = line.first_mut() {
+                    *t = t.trim_start().to_string();
+                }
+            }
+
+            let is_blank = line.iter().all(|n| match n {
+                TextNode::Text(t) => t.trim() == "",
+                _ => false,
+            });
+
+            if is_blank {
+                // a label alone on its line is followed by the section
+                if starts_with_label && line.len() == 0 {
+                    continue;
+                }
+
+                sections.extend(section.take());
+                continue;
+            }
+
+            section
+                .get_or_insert_with(|| Section {
+                    kind: SectionKind::Verse,
+                    number: None,
+                    label: None,
+                    lines: Vec::new(),
+                })
+                .lines
+                .push(line);
+        }
+
+        sections.extend(section);
+
+        sections
+    }
+
+    // drops the chords, the lines which held nothing but chords are left out
+    pub fn without_chords(&self) -> Self {
+        let mut lines: Vec<Vec<TextNode>> = Vec::new();
+
+        for line in self.text.split(|n| n == &TextNode::Newline) {
+            let had_chords = line.iter().any(|n| matches!(n, TextNode::Chord(_)));
+
+            // the bar lines go with the chords, the label in front of them stays
+            let labels = line
+                .iter()
+                .take_while(|n| matches!(n, TextNode::Label(_)))
+                .count();
+            let line = if is_chord_line(&line[labels..]) {
+                &line[..labels]
+            } else {
+                line
+            };
+
+            let mut nodes: Vec<TextNode> = Vec::new();
+
+            for node in line {
+                match (node, nodes.last_mut()) {
+                    (TextNode::Chord(_) | TextNode::Newline, _) => {}
+                    // the text around a chord is joined again, the spaces
+                    // which kept the chords apart are written only once
+                    (TextNode::Text(t), Some(TextNode::Text(previous))) => {
+                        if previous.ends_with(' ') {
+                            *previous += t.trim_start_matches(' ');
+                        } else {
+                            *previous += t;
+                        }
+                    }
+                    (node, _) => nodes.push(node.clone()),
+                }
+            }
+
+            if let Some(TextNode::Text(t)) = nodes.last_mut() {
+                *t = t.trim_end().to_string();
+                if t == "" {
+                    nodes.pop();
+                }
+            }
+
+            // the space after a chord at the start of the line
+            if let (Some(TextNode::Chord(_)), Some(TextNode::Text(t))) =
+                (line.first(), nodes.first_mut())
+            {
+                *t = t.trim_start().to_string();
+                if t == "" {
+                    nodes.remove(0);
+                }
+            }
+
+            if had_chords && nodes.len() == 0 {
+                continue;
+            }
+
+            // the removed lines must not leave two blank lines in a row
+            if nodes.len() == 0 && lines.last().map_or(true, |l| l.len() == 0) {
+                continue;
+            }
+
+            lines.push(nodes);
+        }
+
+        while matches!(lines.last(), Some(l) if l.len() == 0) {
+            lines.pop();
+        }
+
+        Self {
+            text: lines.join(&TextNode::Newline),
+            ..self.clone()
         }
     }
 
     pub fn render_docx<'a>(self) -> Vec<Paragraph<'a>> {
         let mut paragraphs = Vec::new();
 
-        let title_paragraph = Paragraph::default().push(
-            Run::default()
-                .push_text(Text::from((
-                    format!("{} - {}", self.artist, self.song_name),
-                    TextSpace::Preserve,
-                )))
-                .property(CharacterProperty::default().bold(true)),
-        );
+        let title_paragraph = Paragraph::default()
+            .property(ParagraphProperty::default().style_id(TITLE_STYLE))
+            .push(
+                Run::default()
+                    .push_text(Text::from((
+                        format!("{} - {}", self.artist, self.song_name),
+                        TextSpace::Preserve,
+                    )))
+                    .property(CharacterProperty::default().bold(true)),
+            );
 
         paragraphs.push(title_paragraph);
 
@@ -257,3 +614,27 @@ pub enum TextNode {
     Label(String),
     Newline,
 }
+
+// a newline with the indentation around it is a single space,
+// "Hello\n    world" is "Hello world", the spaces in a line are kept
+pub(crate) fn collapse_newlines(text: &str, at_line_start: bool) -> String {
+    let mut output = String::new();
+
+    for (i, part) in text.replace("\r", "").split("\n").enumerate() {
+        let part = if i == 0 {
+            part
+        } else {
+            output.truncate(output.trim_end_matches([' ', '\t']).len());
+            output.push(' ');
+            part.trim_start_matches([' ', '\t'])
+        };
+        output += part;
+    }
+
+    // a line starting in the source on a new line does not start with a space
+    if at_line_start {
+        output.trim_start().to_string()
+    } else {
+        output
+    }
+}
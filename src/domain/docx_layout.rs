@@ -0,0 +1,370 @@
+use std::io::{Cursor, Read, Write};
+
+use docx::document::{Break, BreakType, Paragraph, ParagraphContent, Run};
+use serde::{Deserialize, Serialize};
+use xml::{
+    name::OwnedName,
+    reader::{self, EventReader},
+    writer::{EmitterConfig, EventWriter, XmlEvent},
+};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum PageBreak {
+    // the song starts on a new page
+    #[default]
+    NewPage,
+    // the song follows the previous one on the same page
+    Continue,
+    // the song moves to the next page when it does not fit on the current one
+    KeepTogether,
+    // the song starts in the next column
+    NewColumn,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Paper {
+    #[default]
+    A4,
+    A5,
+    Letter,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageLayout {
+    pub columns: u32,
+    pub column_spacing_mm: f32,
+    pub paper: Paper,
+    pub orientation: Orientation,
+    pub margin_mm: f32,
+}
+
+impl Default for PageLayout {
+    fn default() -> Self {
+        Self {
+            columns: 1,
+            column_spacing_mm: 10.0,
+            paper: Paper::A4,
+            orientation: Orientation::Portrait,
+            margin_mm: 20.0,
+        }
+    }
+}
+
+impl PageLayout {
+    // the songs in columns are not split, so the columns break between them
+    pub fn default_page_break(&self) -> PageBreak {
+        if self.columns > 1 {
+            PageBreak::KeepTogether
+        } else {
+            PageBreak::NewPage
+        }
+    }
+
+    // in millimeters, width and height
+    fn page_size(&self) -> (f32, f32) {
+        let (width, height) = match self.paper {
+            Paper::A4 => (210.0, 297.0),
+            Paper::A5 => (148.0, 210.0),
+            Paper::Letter => (215.9, 279.4),
+        };
+
+        match self.orientation {
+            Orientation::Portrait => (width, height),
+            Orientation::Landscape => (height, width),
+        }
+    }
+
+    // the children of the section properties of the whole document with their attributes,
+    // the sizes are in twentieths of a point
+    fn section_properties(&self) -> Vec<(&'static str, Vec<(&'static str, String)>)> {
+        let twips = |mm: f32| (mm / 25.4 * 1440.0).round() as u32;
+        let (width, height) = self.page_size();
+        let margin = twips(self.margin_mm).to_string();
+        let header = (twips(self.margin_mm) / 2).to_string();
+
+        let mut page_size = vec![
+            ("w", twips(width).to_string()),
+            ("h", twips(height).to_string()),
+        ];
+        if self.orientation == Orientation::Landscape {
+            page_size.push(("orient", "landscape".to_string()));
+        }
+
+        vec![
+            ("pgSz", page_size),
+            (
+                "pgMar",
+                vec![
+                    ("top", margin.clone()),
+                    ("right", margin.clone()),
+                    ("bottom", margin.clone()),
+                    ("left", margin),
+                    ("header", header.clone()),
+                    ("footer", header),
+                    ("gutter", "0".to_string()),
+                ],
+            ),
+            (
+                "cols",
+                vec![
+                    ("num", self.columns.max(1).to_string()),
+                    ("space", twips(self.column_spacing_mm).to_string()),
+                ],
+            ),
+        ]
+    }
+}
+
+// the docx crate can not write the properties which control the page breaks,
+// `pageBreakBefore`, `keepNext` and `keepLines`, they are added to the paragraphs
+// of the written document in the order the paragraphs were pushed
+pub struct DocxLayout;
+
+impl DocxLayout {
+    // the paragraphs of one song, as returned by `LyricsWithChords::render_docx`,
+    // with the names of the properties of every paragraph
+    pub fn song<'a>(
+        paragraphs: Vec<Paragraph<'a>>,
+        page_break: PageBreak,
+        is_first: bool,
+        layout: &PageLayout,
+    ) -> Vec<(Paragraph<'a>, Vec<&'static str>)> {
+        let count = paragraphs.len();
+        let is_blank: Vec<bool> = paragraphs.iter().map(|p| p.content.is_empty()).collect();
+        // a column is too narrow to split the verses
+        let keep_verses = layout.columns > 1;
+
+        let mut song = Vec::new();
+
+        // an empty line between the songs which may share a page
+        if !is_first && matches!(page_break, PageBreak::Continue | PageBreak::KeepTogether) {
+            song.push((Paragraph::default(), Vec::new()));
+        }
+
+        for (i, mut paragraph) in paragraphs.into_iter().enumerate() {
+            let is_title = i == 0;
+            let is_last = i + 1 == count;
+            let in_verse = !is_blank[i] && !is_last && !is_blank[i + 1];
+
+            let mut properties = Vec::new();
+            // the title stays with the first line of the song
+            if is_title
+                || (page_break == PageBreak::KeepTogether && !is_last)
+                || (keep_verses && in_verse)
+            {
+                properties.push("keepNext");
+            }
+            if page_break == PageBreak::KeepTogether || keep_verses {
+                properties.push("keepLines");
+            }
+            // the first page is new anyway
+            if is_title && page_break == PageBreak::NewPage && !is_first {
+                properties.push("pageBreakBefore");
+            }
+
+            if is_title && page_break == PageBreak::NewColumn && !is_first {
+                paragraph.content.insert(
+                    0,
+                    ParagraphContent::Run(
+                        Run::default().push_break(Break::from(BreakType::Column)),
+                    ),
+                );
+            }
+
+            song.push((paragraph, properties));
+        }
+
+        song
+    }
+
+    // writes the properties of every paragraph into a written document
+    // and sets the page layout of the whole document
+    pub fn apply(
+        docx: Vec<u8>,
+        properties: &[Vec<&str>],
+        layout: &PageLayout,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut archive = ZipArchive::new(Cursor::new(docx))?;
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.name() != "word/document.xml" {
+                drop(file);
+                zip.raw_copy_file(archive.by_index_raw(i)?)?;
+                continue;
+            }
+
+            let mut document = Vec::new();
+            file.read_to_end(&mut document)?;
+
+            zip.start_file(
+                file.name(),
+                FileOptions::default().compression_method(file.compression()),
+            )?;
+            zip.write_all(&rewrite_document(&document, properties, layout)?)?;
+        }
+
+        Ok(zip.finish()?.into_inner())
+    }
+}
+
+// the paragraphs and the section properties are the children of <w:body>,
+// the properties go first in <w:pPr>, only the style may be in front of them
+fn rewrite_document(
+    document: &[u8],
+    properties: &[Vec<&str>],
+    layout: &PageLayout,
+) -> anyhow::Result<Vec<u8>> {
+    let events = EventReader::new(document)
+        .into_iter()
+        .collect::<Result<Vec<reader::XmlEvent>, _>>()?;
+
+    let mut output = Vec::new();
+    let mut writer = EmitterConfig::new()
+        .perform_indent(false)
+        .create_writer(&mut output);
+
+    let mut path: Vec<OwnedName> = Vec::new();
+    let mut paragraph = 0;
+    let mut i = 0;
+    while i < events.len() {
+        let event = &events[i];
+        i += 1;
+
+        let in_body = path.last().is_some_and(|name| name.local_name == "body");
+
+        match event {
+            reader::XmlEvent::StartElement { name, .. }
+                if in_body && name.local_name == "sectPr" =>
+            {
+                // the section properties of the document are written again at the end of the body
+                let mut depth = 1;
+                while depth != 0 {
+                    match events.get(i) {
+                        Some(reader::XmlEvent::StartElement { .. }) => depth += 1,
+                        Some(reader::XmlEvent::EndElement { .. }) => depth -= 1,
+                        None => break,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+            reader::XmlEvent::StartElement { name, .. } => {
+                path.push(name.clone());
+                write_event(&mut writer, event)?;
+
+                if !(in_body && name.local_name == "p") {
+                    continue;
+                }
+
+                let paragraph_properties = properties.get(paragraph).map_or(&[][..], |p| &p[..]);
+                paragraph += 1;
+                if paragraph_properties.is_empty() {
+                    continue;
+                }
+
+                let has_properties = matches!(
+                    events.get(i),
+                    Some(reader::XmlEvent::StartElement { name, .. }) if name.local_name == "pPr"
+                );
+                if has_properties {
+                    write_event(&mut writer, &events[i])?;
+                    path.push(element_name(name, "pPr"));
+                    i += 1;
+
+                    if let Some(reader::XmlEvent::StartElement { name, .. }) = events.get(i) {
+                        if name.local_name == "pStyle" {
+                            write_event(&mut writer, &events[i])?;
+                            write_event(&mut writer, &events[i + 1])?;
+                            i += 2;
+                        }
+                    }
+                } else {
+                    writer.write(XmlEvent::start_element(element_name(name, "pPr").borrow()))?;
+                }
+
+                for property in paragraph_properties {
+                    writer.write(XmlEvent::start_element(
+                        element_name(name, property).borrow(),
+                    ))?;
+                    writer.write(XmlEvent::end_element())?;
+                }
+
+                if !has_properties {
+                    writer.write(XmlEvent::end_element())?;
+                }
+            }
+            reader::XmlEvent::EndElement { name } => {
+                path.pop();
+
+                if name.local_name == "body" {
+                    write_section_properties(&mut writer, name, layout)?;
+                }
+                write_event(&mut writer, event)?;
+            }
+            _ => write_event(&mut writer, event)?,
+        }
+    }
+
+    Ok(output)
+}
+
+fn write_event<W: Write>(
+    writer: &mut EventWriter<W>,
+    event: &reader::XmlEvent,
+) -> anyhow::Result<()> {
+    if let Some(event) = event.as_writer_event() {
+        writer.write(event)?;
+    }
+
+    Ok(())
+}
+
+// an element in the same namespace as `sibling`, "w:keepNext"
+fn element_name(sibling: &OwnedName, local_name: &str) -> OwnedName {
+    OwnedName {
+        local_name: local_name.to_string(),
+        namespace: sibling.namespace.clone(),
+        prefix: sibling.prefix.clone(),
+    }
+}
+
+fn write_section_properties<W: Write>(
+    writer: &mut EventWriter<W>,
+    body: &OwnedName,
+    layout: &PageLayout,
+) -> anyhow::Result<()> {
+    let name = |local_name: &str| element_name(body, local_name);
+
+    writer.write(XmlEvent::start_element(name("sectPr").borrow()))?;
+
+    for (element, attributes) in layout.section_properties() {
+        let element = name(element);
+        let attributes: Vec<(OwnedName, String)> = attributes
+            .into_iter()
+            .map(|(attribute, value)| (name(attribute), value))
+            .collect();
+
+        let mut start = XmlEvent::start_element(element.borrow());
+        for (attribute, value) in attributes.iter() {
+            start = start.attr(attribute.borrow(), value);
+        }
+
+        writer.write(start)?;
+        writer.write(XmlEvent::end_element())?;
+    }
+
+    writer.write(XmlEvent::end_element())?;
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use docx::{
+    document::{BodyContent, Paragraph, ParagraphContent, Run, RunContent},
+    formatting::VerticalAlignmentType,
+    Docx,
+};
+
+use super::core::{LyricsWithChords, TextNode, TITLE_STYLE};
+
+// reads back the songbooks written by `write_docx`,
+// every song starts with a bold "Artist - Title" paragraph in the title style
+pub struct DocxSongbook;
+
+impl DocxSongbook {
+    pub fn parse(document: &Docx) -> anyhow::Result<Vec<LyricsWithChords>> {
+        let mut songs = Vec::new();
+        let mut song: Option<(String, String, Vec<Vec<TextNode>>)> = None;
+
+        for content in document.document.body.content.iter() {
+            let paragraph = match content {
+                BodyContent::Paragraph(p) => p,
+                _ => continue,
+            };
+
+            if let Some((artist, song_name)) = title(paragraph) {
+                songs.extend(song.take().map(build_song));
+                song = Some((artist, song_name, Vec::new()));
+                continue;
+            }
+
+            // the text in front of the first title does not belong to any song
+            if let Some((_, _, lines)) = song.as_mut() {
+                lines.push(paragraph_nodes(paragraph));
+            }
+        }
+
+        songs.extend(song.map(build_song));
+
+        if songs.len() == 0 {
+            return Err(anyhow::Error::msg("No songs found in the document!"));
+        }
+
+        Ok(songs)
+    }
+}
+
+fn build_song(
+    (artist, song_name, mut lines): (String, String, Vec<Vec<TextNode>>),
+) -> LyricsWithChords {
+    while matches!(lines.last(), Some(l) if l.len() == 0) {
+        lines.pop();
+    }
+
+    LyricsWithChords::new(lines.join(&TextNode::Newline), artist, song_name)
+}
+
+fn runs<'a, 'b>(paragraph: &'b Paragraph<'a>) -> impl Iterator<Item = &'b Run<'a>> {
+    paragraph
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            ParagraphContent::Run(r) => Some(r),
+            _ => None,
+        })
+}
+
+fn run_text(run: &Run) -> String {
+    run.content
+        .iter()
+        .filter_map(|content| match content {
+            RunContent::Text(t) => Some(t.text.to_string()),
+            // the page breaks between the songs
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_bold(run: &Run) -> bool {
+    run.property
+        .as_ref()
+        .and_then(|p| p.bold.as_ref())
+        .is_some_and(|b| b.value.unwrap_or(true))
+}
+
+fn is_superscript(run: &Run) -> bool {
+    run.property
+        .as_ref()
+        .and_then(|p| p.vertical_alignment.as_ref())
+        .is_some_and(|v| matches!(v.value, VerticalAlignmentType::Superscript))
+}
+
+// a paragraph in the title style made only of bold text, "Artist - Title",
+// a bold line of the lyrics with a dash is not a title
+fn title(paragraph: &Paragraph) -> Option<(String, String)> {
+    let is_title = paragraph
+        .property
+        .as_ref()
+        .and_then(|p| p.style_id.as_ref())
+        .is_some_and(|s| s.value == TITLE_STYLE);
+    if !is_title {
+        return None;
+    }
+
+    let text_runs: Vec<&Run> = runs(paragraph).filter(|r| run_text(r) != "").collect();
+
+    if text_runs.len() == 0 || text_runs.iter().any(|r| !is_bold(r) || is_superscript(r)) {
+        return None;
+    }
+
+    let text: String = text_runs.iter().map(|r| run_text(r)).collect();
+    let (artist, song_name) = text.split_once(" - ")?;
+
+    Some((artist.trim().to_string(), song_name.trim().to_string()))
+}
+
+fn paragraph_nodes(paragraph: &Paragraph) -> Vec<TextNode> {
+    let mut nodes: Vec<TextNode> = Vec::new();
+
+    for run in runs(paragraph) {
+        let text = run_text(run);
+        if text == "" {
+            continue;
+        }
+
+        if is_superscript(run) {
+            // Word may have joined the runs of neighbouring chords
+            for (i, chord) in text.split_whitespace().enumerate() {
+                if i != 0 {
+                    nodes.push(TextNode::Text(" ".to_string()));
+                }
+
+                nodes.push(TextNode::Chord(chord.to_string()));
+            }
+        } else if is_bold(run) && nodes.len() == 0 {
+            nodes.push(TextNode::Label(text));
+        } else {
+            match nodes.last_mut() {
+                // Word splits the text into many runs
+                Some(TextNode::Text(t)) => *t += &text,
+                _ => nodes.push(TextNode::Text(text)),
+            }
+        }
+    }
+
+    nodes
+}
@@ -0,0 +1,233 @@
+use std::{collections::BTreeMap, time::SystemTime};
+
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use sha2::{Digest, Sha256};
+
+use super::{
+    core::{LyricsWithChords, Options},
+    html::{song_title, Html},
+    package::write_package,
+};
+
+// an epub 3 book with one page for every song,
+// the pages use the same markup as the html export
+pub struct Epub;
+
+impl Epub {
+    pub fn render(
+        songs: &[LyricsWithChords],
+        options: Option<Options>,
+        title: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut files = vec![
+            ("META-INF/container.xml".to_string(), CONTAINER.to_string()),
+            ("OEBPS/content.opf".to_string(), package(songs, title)),
+            ("OEBPS/nav.xhtml".to_string(), navigation(songs, title)),
+            (
+                "OEBPS/style.css".to_string(),
+                Html::stylesheet().to_string(),
+            ),
+        ];
+
+        for (i, song) in songs.iter().enumerate() {
+            files.push((
+                format!("OEBPS/{}", page_name(i)),
+                page(&song_title(song), &Html::render_song(song, options.clone())),
+            ));
+        }
+
+        write_package(
+            Some("application/epub+zip"),
+            files
+                .into_iter()
+                .map(|(name, content)| (name, content.into_bytes()))
+                .collect(),
+        )
+    }
+}
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn page_name(index: usize) -> String {
+    format!("song-{}.xhtml", index + 1)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+<meta charset="utf-8"/>
+<title>{}</title>
+<link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+{}
+</body>
+</html>
+"#,
+        encode_text(title),
+        body
+    )
+}
+
+fn package(songs: &[LyricsWithChords], title: &str) -> String {
+    // the same songs always get the same identifier
+    let mut hasher = Sha256::new();
+    for song in songs {
+        hasher.update(song_title(song).as_bytes());
+    }
+    let identifier = format!("spevnik-{:x}", hasher.finalize());
+
+    let mut manifest = vec![
+        r#"<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>"#
+            .to_string(),
+        r#"<item id="style" href="style.css" media-type="text/css"/>"#.to_string(),
+    ];
+    let mut spine = vec![r#"<itemref idref="nav"/>"#.to_string()];
+
+    for i in 0..songs.len() {
+        manifest.push(format!(
+            r#"<item id="song-{}" href="{}" media-type="application/xhtml+xml"/>"#,
+            i + 1,
+            page_name(i)
+        ));
+        spine.push(format!(r#"<itemref idref="song-{}"/>"#, i + 1));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="identifier">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:identifier id="identifier">{}</dc:identifier>
+<dc:title>{}</dc:title>
+<dc:language>sk</dc:language>
+<meta property="dcterms:modified">{}</meta>
+</metadata>
+<manifest>
+{}
+</manifest>
+<spine>
+{}
+</spine>
+</package>
+"#,
+        identifier,
+        encode_text(title),
+        timestamp(SystemTime::now()),
+        manifest.join("\n"),
+        spine.join("\n")
+    )
+}
+
+// the table of contents is sorted by the song titles,
+// the second list groups the songs by their artists
+fn navigation(songs: &[LyricsWithChords], title: &str) -> String {
+    let link = |i: usize, text: &str| {
+        format!(
+            r#"<li><a href="{}">{}</a></li>"#,
+            encode_double_quoted_attribute(&page_name(i)),
+            encode_text(text)
+        )
+    };
+
+    let mut by_title: Vec<(usize, &LyricsWithChords)> = songs.iter().enumerate().collect();
+    by_title.sort_by_key(|(_, song)| song.song_name.to_lowercase());
+
+    let titles: Vec<String> = by_title
+        .iter()
+        .map(|(i, song)| link(*i, &song.song_name))
+        .collect();
+
+    let mut by_artist: BTreeMap<String, (String, Vec<(usize, &LyricsWithChords)>)> =
+        BTreeMap::new();
+    for (i, song) in by_title {
+        by_artist
+            .entry(song.artist.to_lowercase())
+            .or_insert((song.artist.clone(), Vec::new()))
+            .1
+            .push((i, song));
+    }
+
+    let artists: Vec<String> = by_artist
+        .into_values()
+        .map(|(artist, songs)| {
+            let artist = if artist == "" {
+                "?".to_string()
+            } else {
+                artist
+            };
+            let songs: Vec<String> = songs
+                .iter()
+                .map(|(i, song)| link(*i, &song.song_name))
+                .collect();
+
+            format!(
+                "<li><span>{}</span>\n<ol>\n{}\n</ol>\n</li>",
+                encode_text(&artist),
+                songs.join("\n")
+            )
+        })
+        .collect();
+
+    page(
+        title,
+        &format!(
+            r#"<nav epub:type="toc" id="toc">
+<h1>{}</h1>
+<ol>
+{}
+</ol>
+</nav>
+<nav epub:type="index" id="artists">
+<h1>Artists</h1>
+<ol>
+{}
+</ol>
+</nav>"#,
+            encode_text(title),
+            titles.join("\n"),
+            artists.join("\n")
+        ),
+    )
+}
+
+// "2024-05-01T12:00:00Z", the format of dcterms:modified
+fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let days = (seconds / 86400) as i64;
+    let seconds_of_day = seconds % 86400;
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
@@ -0,0 +1,246 @@
+use html_escape::{encode_double_quoted_attribute, encode_text};
+use serde::{Deserialize, Serialize};
+
+use super::core::{LyricsWithChords, Options, Section, SectionKind, TextNode};
+
+const STYLESHEET: &str = r#"
+body { font-family: sans-serif; line-height: 1.3; margin: 2em auto; max-width: 50em; padding: 0 1em; }
+.toc ol { padding-left: 1.5em; }
+.toc a { color: inherit; }
+.song { margin-bottom: 3em; }
+.song h1 { font-size: 1.4em; margin-bottom: 0.2em; }
+.song .metadata { color: #555; font-size: 0.9em; margin-top: 0; }
+.section { margin: 0 0 1em 0; }
+.section.chorus { border-left: 3px solid #888; padding-left: 0.8em; }
+.section.chorus.repeat { border-left: none; padding-left: 0; }
+.label { font-weight: bold; margin-right: 0.3em; }
+.line { white-space: pre-wrap; }
+.chunk { display: inline-flex; flex-direction: column; vertical-align: bottom; }
+.chord { font-weight: bold; font-size: 0.85em; padding-right: 0.3em; }
+@media print {
+  body { margin: 0; max-width: none; }
+  .toc { break-after: page; }
+  .song { break-before: page; margin-bottom: 0; }
+  .section, .line { break-inside: avoid; }
+  .toc a { text-decoration: none; }
+}
+"#;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HtmlOptions {
+    // the title of the songbook
+    pub title: Option<String>,
+    pub table_of_contents: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            table_of_contents: true,
+        }
+    }
+}
+
+pub struct Html;
+
+impl Html {
+    // a standalone page with the stylesheet included
+    pub fn render_songbook(
+        songs: &[LyricsWithChords],
+        options: Option<Options>,
+        html_options: &HtmlOptions,
+    ) -> String {
+        let title = html_options.title.clone().unwrap_or("Songbook".to_string());
+
+        let mut output = vec![
+            "<!DOCTYPE html>".to_string(),
+            "<html>".to_string(),
+            "<head>".to_string(),
+            "<meta charset=\"utf-8\">".to_string(),
+            format!("<title>{}</title>", encode_text(&title)),
+            format!("<style>{}</style>", STYLESHEET),
+            "</head>".to_string(),
+            "<body>".to_string(),
+        ];
+
+        if html_options.table_of_contents && songs.len() > 1 {
+            output.push("<nav class=\"toc\">".to_string());
+            output.push(format!("<h1>{}</h1>", encode_text(&title)));
+            output.push("<ol>".to_string());
+
+            for (i, song) in songs.iter().enumerate() {
+                output.push(format!(
+                    "<li><a href=\"#song-{}\">{}</a></li>",
+                    i + 1,
+                    encode_text(&song_title(song))
+                ));
+            }
+
+            output.push("</ol>".to_string());
+            output.push("</nav>".to_string());
+        }
+
+        for (i, song) in songs.iter().enumerate() {
+            output.push(Self::render_song_with_id(
+                song,
+                options.clone(),
+                &format!("song-{}", i + 1),
+            ));
+        }
+
+        output.push("</body>".to_string());
+        output.push("</html>".to_string());
+
+        output.join("\n") + "\n"
+    }
+
+    // a fragment for the preview, styled by `Html::stylesheet`
+    pub fn render_song(song: &LyricsWithChords, options: Option<Options>) -> String {
+        Self::render_song_with_id(song, options, "song")
+    }
+
+    pub fn stylesheet() -> &'static str {
+        STYLESHEET
+    }
+
+    fn render_song_with_id(song: &LyricsWithChords, options: Option<Options>, id: &str) -> String {
+        let user_options = options.unwrap_or_default();
+
+        let mut output = vec![
+            format!(
+                "<article class=\"song\" id=\"{}\">",
+                encode_double_quoted_attribute(id)
+            ),
+            format!("<h1>{}</h1>", encode_text(&song_title(song))),
+        ];
+
+        let metadata = &song.metadata;
+        let details: Vec<String> = [
+            metadata.lyricist.as_ref().map(|l| format!("Words: {}", l)),
+            metadata.composer.as_ref().map(|c| format!("Music: {}", c)),
+            metadata.key.as_ref().map(|k| format!("Key: {}", k)),
+            metadata.capo.map(|c| format!("Capo: {}", c)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if details.len() != 0 {
+            output.push(format!(
+                "<p class=\"metadata\">{}</p>",
+                encode_text(&details.join(" · "))
+            ));
+        }
+
+        for section in song.sections(&user_options) {
+            output.push(render_section(&section));
+        }
+
+        output.push("</article>".to_string());
+
+        output.join("\n")
+    }
+}
+
+pub(crate) fn song_title(song: &LyricsWithChords) -> String {
+    if song.artist == "" {
+        song.song_name.clone()
+    } else {
+        format!("{} - {}", song.artist, song.song_name)
+    }
+}
+
+fn section_class(kind: SectionKind) -> &'static str {
+    match kind {
+        SectionKind::Verse => "verse",
+        SectionKind::Chorus => "chorus",
+        SectionKind::PreChorus => "pre-chorus",
+        SectionKind::PostChorus => "post-chorus",
+        SectionKind::Bridge => "bridge",
+        SectionKind::Intro => "intro",
+        SectionKind::Outro => "outro",
+        SectionKind::Interlude => "interlude",
+        SectionKind::Instrumental => "instrumental",
+        SectionKind::Solo => "solo",
+        SectionKind::Other => "other",
+    }
+}
+
+fn render_section(section: &Section) -> String {
+    let mut class = format!("section {}", section_class(section.kind));
+
+    // a label without any lines repeats an earlier section
+    if section.lines.len() == 0 {
+        class += " repeat";
+    }
+
+    let mut output = vec![format!("<div class=\"{}\">", class)];
+
+    for (i, line) in section.lines.iter().enumerate() {
+        let label = match &section.label {
+            Some(label) if i == 0 => Some(label.as_str()),
+            _ => None,
+        };
+
+        output.push(render_line(label, line));
+    }
+
+    if let (Some(label), 0) = (&section.label, section.lines.len()) {
+        output.push(render_line(Some(label), &[]));
+    }
+
+    output.push("</div>".to_string());
+
+    output.join("\n")
+}
+
+// every chord starts a chunk, which holds it above the rest of the word
+fn render_line(label: Option<&str>, line: &[TextNode]) -> String {
+    let mut output = "<div class=\"line\">".to_string();
+
+    if let Some(label) = label {
+        output += &format!("<span class=\"label\">{}</span>", encode_text(label));
+    }
+
+    let mut chunk: Option<(String, String)> = None;
+    let close_chunk = |output: &mut String, chunk: Option<(String, String)>| {
+        if let Some((chord, text)) = chunk {
+            // the space keeps the line under a chord without lyrics
+            let text = if text == "" { " ".to_string() } else { text };
+
+            *output += &format!(
+                "<span class=\"chunk\"><span class=\"chord\">{}</span><span class=\"lyrics\">{}</span></span>",
+                encode_text(&chord),
+                encode_text(&text)
+            );
+        }
+    };
+
+    for node in line {
+        match node {
+            TextNode::Chord(ch) => {
+                close_chunk(&mut output, chunk.replace((ch.clone(), String::new())))
+            }
+            TextNode::Text(t) | TextNode::Label(t) => match chunk.as_mut() {
+                // the chunk ends with the word, so the rest of the line can wrap
+                Some((_, text)) => match t.find(char::is_whitespace) {
+                    Some(i) => {
+                        *text += &t[..i];
+                        close_chunk(&mut output, chunk.take());
+                        output += &encode_text(&t[i..]);
+                    }
+                    None => *text += t,
+                },
+                None => output += &encode_text(t),
+            },
+            TextNode::Newline => {}
+        }
+    }
+    close_chunk(&mut output, chunk);
+
+    output += "</div>";
+
+    output
+}
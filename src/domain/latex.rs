@@ -0,0 +1,172 @@
+use unicode_normalization::UnicodeNormalization;
+
+use super::core::{LyricsWithChords, Options, Section, SectionKind, TextNode};
+
+// a songbook for the `songs` package, http://songs.sourceforge.net/
+pub struct Latex;
+
+impl Latex {
+    pub fn render_songbook(songs: &[LyricsWithChords], options: Option<Options>) -> String {
+        let mut output = vec![
+            "\\documentclass{article}".to_string(),
+            "\\usepackage[T1]{fontenc}".to_string(),
+            "\\usepackage[chorded]{songs}".to_string(),
+            "".to_string(),
+            "\\newindex{titleidx}{titles}".to_string(),
+            "\\newauthorindex{authidx}{authors}".to_string(),
+            "".to_string(),
+            "\\begin{document}".to_string(),
+            "\\showindex{Songs}{titleidx}".to_string(),
+            "\\showindex{Artists}{authidx}".to_string(),
+            "".to_string(),
+            "\\begin{songs}{titleidx,authidx}".to_string(),
+        ];
+
+        for song in songs {
+            output.push(Self::render_song(song, options.clone()));
+        }
+
+        output.push("\\end{songs}".to_string());
+        output.push("\\end{document}".to_string());
+
+        output.join("\n") + "\n"
+    }
+
+    pub fn render_song(song: &LyricsWithChords, options: Option<Options>) -> String {
+        let user_options = options.unwrap_or_default();
+
+        let mut output = vec![match song.artist.as_str() {
+            "" => format!("\\beginsong{{{}}}", escape(&song.song_name)),
+            artist => format!(
+                "\\beginsong{{{}}}[by={{{}}}]",
+                escape(&song.song_name),
+                escape(artist)
+            ),
+        }];
+
+        if let Some(capo) = song.metadata.capo {
+            output.push(format!("\\capo{{{}}}", capo));
+        }
+
+        for section in song.sections(&user_options) {
+            output.push(render_section(&section));
+        }
+
+        output.push("\\endsong".to_string());
+
+        output.join("\n") + "\n"
+    }
+}
+
+// the package numbers the verses and marks the choruses by itself,
+// so only the labels of the other sections are written
+fn render_section(section: &Section) -> String {
+    let label = section.label.as_ref().map(|l| escape(l.trim()));
+
+    // a label without any lines repeats an earlier section
+    if section.lines.len() == 0 {
+        return match label {
+            Some(label) => format!("\\textnote{{{}}}", label),
+            None => String::new(),
+        };
+    }
+
+    let (begin, end) = match section.kind {
+        SectionKind::Verse => ("\\beginverse", "\\endverse"),
+        SectionKind::Chorus => ("\\beginchorus", "\\endchorus"),
+        _ => ("\\beginverse*", "\\endverse"),
+    };
+
+    let mut output = vec![begin.to_string()];
+
+    match (section.kind, label) {
+        (SectionKind::Verse | SectionKind::Chorus, _) | (_, None) => {}
+        (_, Some(label)) => output.push(format!("\\textnote{{{}}}", label)),
+    }
+
+    for line in section.lines.iter() {
+        output.push(
+            line.iter()
+                .map(|n| match n {
+                    TextNode::Text(t) | TextNode::Label(t) => escape(t),
+                    TextNode::Chord(ch) => format!("\\[{}]", escape_chord(ch)),
+                    TextNode::Newline => unreachable!(),
+                })
+                .collect(),
+        );
+    }
+
+    output.push(end.to_string());
+
+    output.join("\n")
+}
+
+// inside of a chord "#" is the sharp sign, "&" the flat sign
+fn escape_chord(chord: &str) -> String {
+    chord
+        .split("#")
+        .map(escape)
+        .collect::<Vec<String>>()
+        .join("#")
+}
+
+// the special characters are escaped and the letters with diacritics are written
+// with accent commands ("č" -> "\v{c}"), so the output does not depend on inputenc
+fn escape(text: &str) -> String {
+    let mut output = String::new();
+
+    for c in text.chars() {
+        let mut decomposed = c.nfd();
+        let base = decomposed.next().unwrap_or(c);
+        let accents: Vec<char> = decomposed.collect();
+
+        let mut escaped = match base {
+            '\\' => "\\textbackslash{}".to_string(),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => format!("\\{}", base),
+            '~' => "\\textasciitilde{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            // the accents go above the dotless i
+            'i' if accents.len() != 0 => "\\i".to_string(),
+            '„' => ",,".to_string(),
+            '“' => "``".to_string(),
+            '”' => "''".to_string(),
+            '–' => "--".to_string(),
+            '—' => "---".to_string(),
+            '…' => "\\ldots{}".to_string(),
+            '®' => "\\textregistered{}".to_string(),
+            '\u{a0}' => "~".to_string(),
+            base => base.to_string(),
+        };
+
+        let commands: Option<Vec<&str>> = accents.iter().map(|a| accent_command(*a)).collect();
+        match commands {
+            Some(commands) => {
+                for command in commands {
+                    escaped = format!("\\{}{{{}}}", command, escaped);
+                }
+            }
+            // an accent LaTeX has no command for
+            None => escaped = c.to_string(),
+        }
+
+        output += &escaped;
+    }
+
+    output
+}
+
+fn accent_command(accent: char) -> Option<&'static str> {
+    match accent {
+        '\u{301}' => Some("'"),
+        '\u{300}' => Some("`"),
+        '\u{302}' => Some("^"),
+        '\u{303}' => Some("~"),
+        '\u{308}' => Some("\""),
+        '\u{30a}' => Some("r"),
+        '\u{30b}' => Some("H"),
+        '\u{30c}' => Some("v"),
+        '\u{327}' => Some("c"),
+        '\u{328}' => Some("k"),
+        _ => None,
+    }
+}
@@ -1,3 +1,17 @@
+pub mod alignment;
+pub mod chordpro;
 pub mod core;
+pub mod docx_layout;
+pub mod docx_songbook;
+pub mod epub;
+pub mod html;
+pub mod latex;
+pub mod odt;
+pub mod onsong;
+pub mod openlyrics;
+mod package;
+pub mod pdf;
+pub mod plain_text;
+pub mod pptx;
 pub mod supermusic;
 pub mod ultimate_guitar;
@@ -0,0 +1,183 @@
+use std::io::Write;
+
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+use super::{
+    core::{LyricsWithChords, TextNode},
+    html::song_title,
+    package::write_package,
+};
+
+const OFFICE: &str = "urn:oasis:names:tc:opendocument:xmlns:office:1.0";
+const TEXT: &str = "urn:oasis:names:tc:opendocument:xmlns:text:1.0";
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.3">
+  <manifest:file-entry manifest:full-path="/" manifest:version="1.3" manifest:media-type="application/vnd.oasis.opendocument.text"/>
+  <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+  <manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+// the same formatting as `LyricsWithChords::render_docx`,
+// the names can be changed in the document to restyle every song at once
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-styles xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" office:version="1.3">
+  <office:styles>
+    <style:style style:name="Lyrics" style:family="paragraph"/>
+    <style:style style:name="Song_20_Title" style:display-name="Song Title" style:family="paragraph" style:next-style-name="Lyrics">
+      <style:paragraph-properties fo:keep-with-next="always"/>
+      <style:text-properties fo:font-weight="bold" style:font-weight-asian="bold" style:font-weight-complex="bold"/>
+    </style:style>
+    <style:style style:name="Song_20_Title_20_New_20_Page" style:display-name="Song Title New Page" style:family="paragraph" style:parent-style-name="Song_20_Title" style:next-style-name="Lyrics">
+      <style:paragraph-properties fo:break-before="page"/>
+    </style:style>
+    <style:style style:name="Chord" style:family="text">
+      <style:text-properties fo:font-weight="bold" style:font-weight-asian="bold" style:font-weight-complex="bold" style:text-position="super 58%"/>
+    </style:style>
+    <style:style style:name="Label" style:family="text">
+      <style:text-properties fo:font-weight="bold" style:font-weight-asian="bold" style:font-weight-complex="bold"/>
+    </style:style>
+  </office:styles>
+</office:document-styles>
+"#;
+
+// an OpenDocument text, every song after the first one starts on a new page
+pub struct Odt;
+
+impl Odt {
+    pub fn render(songs: &[LyricsWithChords]) -> anyhow::Result<Vec<u8>> {
+        write_package(
+            Some("application/vnd.oasis.opendocument.text"),
+            vec![
+                (
+                    "META-INF/manifest.xml".to_string(),
+                    MANIFEST.as_bytes().to_vec(),
+                ),
+                ("styles.xml".to_string(), STYLES.as_bytes().to_vec()),
+                ("content.xml".to_string(), content(songs)?),
+            ],
+        )
+    }
+}
+
+fn content(songs: &[LyricsWithChords]) -> anyhow::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut writer = EmitterConfig::new()
+        .perform_indent(false)
+        .create_writer(&mut output);
+
+    writer.write(
+        XmlEvent::start_element("office:document-content")
+            .ns("office", OFFICE)
+            .ns("text", TEXT)
+            .attr("office:version", "1.3"),
+    )?;
+    writer.write(XmlEvent::start_element("office:body"))?;
+    writer.write(XmlEvent::start_element("office:text"))?;
+
+    for (i, song) in songs.iter().enumerate() {
+        let title_style = if i == 0 {
+            "Song_20_Title"
+        } else {
+            "Song_20_Title_20_New_20_Page"
+        };
+
+        writer.write(XmlEvent::start_element("text:p").attr("text:style-name", title_style))?;
+        write_text(&mut writer, &song_title(song))?;
+        writer.write(XmlEvent::end_element())?;
+
+        for line in song.text.split(|n| n == &TextNode::Newline) {
+            writer.write(XmlEvent::start_element("text:p").attr("text:style-name", "Lyrics"))?;
+
+            for node in line {
+                match node {
+                    TextNode::Text(t) => write_text(&mut writer, t)?,
+                    TextNode::Chord(ch) => write_span(&mut writer, "Chord", ch)?,
+                    TextNode::Label(l) => write_span(&mut writer, "Label", l)?,
+                    TextNode::Newline => {}
+                }
+            }
+
+            writer.write(XmlEvent::end_element())?;
+        }
+    }
+
+    writer.write(XmlEvent::end_element())?;
+    writer.write(XmlEvent::end_element())?;
+    writer.write(XmlEvent::end_element())?;
+
+    Ok(output)
+}
+
+fn write_span<W: Write>(
+    writer: &mut EventWriter<W>,
+    style: &str,
+    text: &str,
+) -> anyhow::Result<()> {
+    writer.write(XmlEvent::start_element("text:span").attr("text:style-name", style))?;
+    write_text(writer, text)?;
+    writer.write(XmlEvent::end_element())?;
+
+    Ok(())
+}
+
+// the spaces are collapsed like in html, so the repeated ones
+// and the ones at the start of a paragraph are written as <text:s/>
+fn write_text<W: Write>(writer: &mut EventWriter<W>, text: &str) -> anyhow::Result<()> {
+    let mut word = String::new();
+    let mut spaces = 0;
+
+    let flush = |writer: &mut EventWriter<W>, word: &mut String| -> anyhow::Result<()> {
+        if word.len() != 0 {
+            writer.write(XmlEvent::characters(word))?;
+            word.clear();
+        }
+        Ok(())
+    };
+
+    for c in text.chars() {
+        if c == ' ' {
+            spaces += 1;
+            continue;
+        }
+
+        // the spaces in front of a tab are written before it
+        if spaces != 0 {
+            // the first space stays in the text unless the text starts with it
+            if word.len() != 0 {
+                word.push(' ');
+                spaces -= 1;
+            }
+
+            if spaces != 0 {
+                flush(writer, &mut word)?;
+                write_spaces(writer, spaces)?;
+            }
+            spaces = 0;
+        }
+
+        if c == '\t' {
+            flush(writer, &mut word)?;
+            writer.write(XmlEvent::start_element("text:tab"))?;
+            writer.write(XmlEvent::end_element())?;
+        } else {
+            word.push(c);
+        }
+    }
+
+    flush(writer, &mut word)?;
+    if spaces != 0 {
+        write_spaces(writer, spaces)?;
+    }
+
+    Ok(())
+}
+
+fn write_spaces<W: Write>(writer: &mut EventWriter<W>, count: usize) -> anyhow::Result<()> {
+    let count = count.to_string();
+    writer.write(XmlEvent::start_element("text:s").attr("text:c", &count))?;
+    writer.write(XmlEvent::end_element())?;
+
+    Ok(())
+}
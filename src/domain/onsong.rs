@@ -0,0 +1,232 @@
+use super::{
+    chordpro::parse_line,
+    core::{
+        LyricsWithChords, Options, Section, SectionCounter, SectionKind, SongMetadata, TextNode,
+    },
+};
+
+pub struct OnSong;
+
+impl OnSong {
+    pub const EXTENSION: &'static str = "onsong";
+
+    // the header lasts until the first blank line, its first untagged line
+    // is the title and the second one the artist
+    pub fn parse(input: &str, options: Option<Options>) -> anyhow::Result<LyricsWithChords> {
+        let user_options = options.unwrap_or_default();
+
+        let mut song_name = None;
+        let mut artist = None;
+        let mut metadata = SongMetadata::default();
+        let mut lines: Vec<Vec<TextNode>> = Vec::new();
+        let mut section_counter = SectionCounter::default();
+        let mut in_header = true;
+        // blank lines in front of the header are skipped
+        let mut header_started = false;
+
+        for line in input.replace("\r\n", "\n").split("\n") {
+            let trimmed_line = line.trim();
+
+            if in_header {
+                if trimmed_line == "" {
+                    in_header = !header_started;
+                    continue;
+                }
+
+                if let Some((tag, value)) = metadata_tag(trimmed_line) {
+                    header_started = true;
+                    let value = Some(value.to_string()).filter(|v| v != "");
+                    match tag.as_str() {
+                        "title" => song_name = value,
+                        "artist" => artist = value,
+                        "author" | "lyricist" | "words" => metadata.lyricist = value,
+                        "composer" | "music" => metadata.composer = value,
+                        "album" | "book" => metadata.album = value,
+                        "key" => metadata.key = value,
+                        "capo" => metadata.capo = value.and_then(|v| v.parse().ok()),
+                        _ => {}
+                    }
+
+                    continue;
+                }
+
+                if section_heading(trimmed_line).is_none() && !trimmed_line.contains("[") {
+                    if song_name.is_none() {
+                        song_name = Some(trimmed_line.to_string());
+                        header_started = true;
+                        continue;
+                    } else if artist.is_none() {
+                        artist = Some(trimmed_line.to_string());
+                        continue;
+                    }
+                }
+
+                in_header = false;
+            }
+
+            if let Some(heading) = section_heading(trimmed_line) {
+                if matches!(lines.last(), Some(l) if l.len() != 0) {
+                    lines.push(vec![]);
+                }
+
+                let label = match SectionKind::from_label(heading) {
+                    // unknown headings, like "Tag:", are kept as they are
+                    (SectionKind::Other, _) => Some(heading.to_string()),
+                    (kind, number) => section_counter.relabel(&user_options, kind, number, heading),
+                };
+
+                if let Some(label) = label {
+                    lines.push(vec![TextNode::Label(label)]);
+                }
+
+                continue;
+            }
+
+            lines.push(match trimmed_line {
+                "" => vec![],
+                _ => parse_line(line.trim_end())?,
+            });
+        }
+
+        while matches!(lines.first(), Some(l) if l.len() == 0) {
+            lines.remove(0);
+        }
+
+        while matches!(lines.last(), Some(l) if l.len() == 0) {
+            lines.pop();
+        }
+
+        let mut lyrics = LyricsWithChords::new(
+            lines.join(&TextNode::Newline),
+            artist.unwrap_or_default(),
+            song_name.unwrap_or_default(),
+        );
+        lyrics.metadata = metadata;
+
+        Ok(lyrics)
+    }
+
+    pub fn render(song: &LyricsWithChords, options: Option<Options>) -> String {
+        let user_options = options.unwrap_or_default();
+
+        // without a title, the artist on the first line would be read as the title
+        let mut output = Vec::new();
+        if song.song_name != "" {
+            output.push(song.song_name.clone());
+            if song.artist != "" {
+                output.push(song.artist.clone());
+            }
+        } else {
+            output.push("Title:".to_string());
+            if song.artist != "" {
+                output.push(format!("Artist: {}", song.artist));
+            }
+        }
+
+        let metadata = &song.metadata;
+        let tags = [
+            ("Lyricist", metadata.lyricist.clone()),
+            ("Composer", metadata.composer.clone()),
+            ("Album", metadata.album.clone()),
+            ("Key", metadata.key.clone()),
+            ("Capo", metadata.capo.map(|c| c.to_string())),
+        ];
+        for (tag, value) in tags {
+            if let Some(value) = value.filter(|v| v != "") {
+                output.push(format!("{}: {}", tag, value));
+            }
+        }
+
+        for section in song.sections(&user_options) {
+            output.push("".to_string());
+
+            if section.label.is_some() {
+                output.push(format!("{}:", heading_name(&section)));
+            }
+
+            for line in section.lines {
+                output.push(
+                    line.iter()
+                        .map(|n| match n {
+                            TextNode::Text(t) => t.clone(),
+                            TextNode::Chord(ch) => format!("[{}]", ch),
+                            TextNode::Label(l) => l.clone(),
+                            TextNode::Newline => unreachable!(),
+                        })
+                        .collect(),
+                );
+            }
+        }
+
+        output.join("\n") + "\n"
+    }
+}
+
+// "Key: G" -> ("key", "G")
+fn metadata_tag(line: &str) -> Option<(String, &str)> {
+    let (tag, value) = line.split_once(":")?;
+    let tag = tag.trim().to_lowercase();
+
+    let tags = [
+        "title",
+        "artist",
+        "author",
+        "lyricist",
+        "words",
+        "composer",
+        "music",
+        "album",
+        "book",
+        "key",
+        "capo",
+        "tempo",
+        "time",
+        "duration",
+        "copyright",
+        "ccli",
+        "keywords",
+        "topic",
+    ];
+
+    if !tags.contains(&tag.as_str()) {
+        return None;
+    }
+
+    Some((tag, value.trim()))
+}
+
+// "Verse 1:" -> "Verse 1", a short line ending with a colon
+fn section_heading(line: &str) -> Option<&str> {
+    let heading = line.strip_suffix(":")?.trim();
+
+    if heading == "" || heading.contains("[") || heading.split_whitespace().count() > 3 {
+        return None;
+    }
+
+    Some(heading)
+}
+
+// our labels ("®:", "1.") mean nothing to onsong, so the headings are named by the kind
+fn heading_name(section: &Section) -> String {
+    let name = match section.kind {
+        SectionKind::Verse => "Verse",
+        SectionKind::Chorus => "Chorus",
+        SectionKind::PreChorus => "Pre-Chorus",
+        SectionKind::PostChorus => "Post-Chorus",
+        SectionKind::Bridge => "Bridge",
+        SectionKind::Intro => "Intro",
+        SectionKind::Outro => "Outro",
+        SectionKind::Interlude => "Interlude",
+        SectionKind::Instrumental => "Instrumental",
+        SectionKind::Solo => "Solo",
+        SectionKind::Other => {
+            let label = section.label.clone().unwrap_or_default();
+            return label.trim().trim_end_matches(":").to_string();
+        }
+    };
+
+    match section.number {
+        Some(number) => format!("{} {}", name, number),
+        None => name.to_string(),
+    }
+}
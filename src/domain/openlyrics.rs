@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use xml::{
+    reader::{self, EventReader, ParserConfig},
+    writer::{EmitterConfig, EventWriter, XmlEvent},
+};
+
+use super::core::{
+    collapse_newlines, LyricsWithChords, Options, SectionKind, SongMetadata, TextNode,
+};
+
+pub struct OpenLyrics;
+
+impl OpenLyrics {
+    pub const NAMESPACE: &'static str = "http://openlyrics.info/namespace/2009/song";
+
+    pub fn render(song: &LyricsWithChords, options: Option<Options>) -> anyhow::Result<String> {
+        let user_options = options.unwrap_or_default();
+
+        let mut output = Vec::new();
+        let mut writer = EmitterConfig::new()
+            .perform_indent(false)
+            .create_writer(&mut output);
+
+        writer.write(
+            XmlEvent::start_element("song")
+                .default_ns(Self::NAMESPACE)
+                .attr("version", "0.9")
+                .attr("createdIn", "spevnikovac-3000"),
+        )?;
+
+        writer.write(XmlEvent::start_element("properties"))?;
+
+        writer.write(XmlEvent::start_element("titles"))?;
+        write_element(&mut writer, "title", &[], &song.song_name)?;
+        writer.write(XmlEvent::end_element())?;
+
+        let metadata = &song.metadata;
+        let authors = [
+            (None, Some(&song.artist)),
+            (Some("words"), metadata.lyricist.as_ref()),
+            (Some("music"), metadata.composer.as_ref()),
+        ];
+        if authors
+            .iter()
+            .any(|(_, a)| matches!(a, Some(a) if *a != ""))
+        {
+            writer.write(XmlEvent::start_element("authors"))?;
+
+            for (author_type, author) in authors {
+                let Some(author) = author.filter(|a| *a != "") else {
+                    continue;
+                };
+
+                let attributes: Vec<(&str, &str)> =
+                    author_type.iter().map(|t| ("type", *t)).collect();
+                write_element(&mut writer, "author", &attributes, author)?;
+            }
+
+            writer.write(XmlEvent::end_element())?;
+        }
+
+        if let Some(album) = &metadata.album {
+            writer.write(XmlEvent::start_element("songbooks"))?;
+            writer.write(XmlEvent::start_element("songbook").attr("name", album))?;
+            writer.write(XmlEvent::end_element())?;
+            writer.write(XmlEvent::end_element())?;
+        }
+
+        if let Some(key) = &metadata.key {
+            write_element(&mut writer, "key", &[], key)?;
+        }
+
+        // openlyrics has no place for the capo
+        if let Some(capo) = metadata.capo {
+            writer.write(XmlEvent::start_element("comments"))?;
+            write_element(&mut writer, "comment", &[], &format!("Capo {}", capo))?;
+            writer.write(XmlEvent::end_element())?;
+        }
+
+        // repeated sections are only written once and referenced in the verse order
+        let mut verses: Vec<(String, Vec<Vec<TextNode>>)> = Vec::new();
+        let mut verse_order = Vec::new();
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for section in song.sections(&user_options) {
+            let prefix = verse_name_prefix(section.kind);
+
+            if section.lines.len() == 0 {
+                if let Some((name, _)) = verses.iter().rev().find(|(n, _)| n.starts_with(prefix)) {
+                    verse_order.push(name.clone());
+                }
+
+                continue;
+            }
+
+            let count = counts.entry(prefix).or_insert(0);
+            *count += 1;
+
+            let name = format!("{}{}", prefix, count);
+            verse_order.push(name.clone());
+            verses.push((name, section.lines));
+        }
+
+        if verse_order.len() != verses.len() {
+            write_element(&mut writer, "verseOrder", &[], &verse_order.join(" "))?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        writer.write(XmlEvent::start_element("lyrics"))?;
+        for (name, lines) in verses {
+            writer.write(XmlEvent::start_element("verse").attr("name", &name))?;
+            writer.write(XmlEvent::start_element("lines"))?;
+
+            for (i, line) in lines.iter().enumerate() {
+                if i != 0 {
+                    writer.write(XmlEvent::start_element("br"))?;
+                    writer.write(XmlEvent::end_element())?;
+                }
+
+                for node in line {
+                    match node {
+                        TextNode::Text(t) | TextNode::Label(t) => {
+                            writer.write(XmlEvent::characters(t))?
+                        }
+                        TextNode::Chord(ch) => {
+                            writer.write(XmlEvent::start_element("chord").attr("name", ch))?;
+                            writer.write(XmlEvent::end_element())?;
+                        }
+                        TextNode::Newline => unreachable!(),
+                    }
+                }
+            }
+
+            writer.write(XmlEvent::end_element())?;
+            writer.write(XmlEvent::end_element())?;
+        }
+        writer.write(XmlEvent::end_element())?;
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(String::from_utf8(output)?)
+    }
+
+    pub fn parse(input: &str, options: Option<Options>) -> anyhow::Result<LyricsWithChords> {
+        let user_options = options.unwrap_or_default();
+
+        let reader = EventReader::new_with_config(
+            input.as_bytes(),
+            ParserConfig::new()
+                .trim_whitespace(false)
+                .whitespace_to_characters(true)
+                .cdata_to_characters(true),
+        );
+
+        let mut path: Vec<String> = Vec::new();
+        let mut song_name = None;
+        let mut artist = None;
+        let mut metadata = SongMetadata::default();
+        let mut verse_order = Vec::new();
+
+        let mut verses: Vec<(String, Vec<Vec<TextNode>>)> = Vec::new();
+        let mut line: Vec<TextNode> = Vec::new();
+        let mut author_type = None;
+
+        for event in reader {
+            match event? {
+                reader::XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    let attribute = |attribute_name: &str| {
+                        attributes
+                            .iter()
+                            .find(|a| a.name.local_name == attribute_name)
+                            .map(|a| a.value.clone())
+                    };
+
+                    match name.local_name.as_str() {
+                        "verse" => verses.push((attribute("name").unwrap_or_default(), vec![])),
+                        "lines" | "br" if path.contains(&"verse".to_string()) => {
+                            end_line(&mut verses, &mut line)
+                        }
+                        "chord" => {
+                            // openlyrics 0.9 splits the chord into its parts
+                            let chord = attribute("name").or(attribute("root").map(|root| {
+                                let mut chord = root + &attribute("structure").unwrap_or_default();
+                                if let Some(bass) = attribute("bass") {
+                                    chord += &format!("/{}", bass);
+                                }
+
+                                chord
+                            }));
+
+                            if let Some(chord) = chord {
+                                line.push(TextNode::Chord(chord));
+                            }
+                        }
+                        "author" => author_type = attribute("type"),
+                        "songbook" if metadata.album.is_none() => {
+                            metadata.album = attribute("name")
+                        }
+                        _ => {}
+                    }
+
+                    path.push(name.local_name);
+                }
+                reader::XmlEvent::EndElement { name } => {
+                    path.pop();
+
+                    if name.local_name == "lines" {
+                        end_line(&mut verses, &mut line);
+                    }
+                }
+                reader::XmlEvent::Characters(text) => {
+                    let Some(element) = path.last().map(|e| e.as_str()) else {
+                        continue;
+                    };
+
+                    let in_lines = path.iter().any(|e| e == "lines");
+                    let value = text.trim().to_string();
+                    match element {
+                        // comments inside of the lyrics are not sung
+                        "comment" if in_lines => {}
+                        _ if in_lines => {
+                            // newlines only format the file, lines are broken by <br/>
+                            let text = collapse_newlines(&text, line.len() == 0);
+
+                            if text != "" {
+                                line.push(TextNode::Text(text));
+                            }
+                        }
+                        "title" if song_name.is_none() => song_name = Some(value),
+                        "author" => match author_type.as_deref() {
+                            Some("words") => metadata.lyricist = Some(value),
+                            Some("music") => metadata.composer = Some(value),
+                            _ if artist.is_none() => artist = Some(value),
+                            _ => {}
+                        },
+                        "key" => metadata.key = Some(value),
+                        "comment" => {
+                            let lowercase = value.to_lowercase();
+                            if let Some(capo) = lowercase.strip_prefix("capo") {
+                                metadata.capo = capo.trim().parse().ok();
+                            }
+                        }
+                        "verseOrder" => {
+                            verse_order = value.split_whitespace().map(|v| v.to_string()).collect()
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if verses.len() == 0 {
+            return Err(anyhow::Error::msg("No verses in the OpenLyrics file!"));
+        }
+
+        if verse_order.len() == 0 {
+            verse_order = verses.iter().map(|(name, _)| name.clone()).collect();
+        }
+
+        let mut lines: Vec<Vec<TextNode>> = Vec::new();
+        let mut written_verses = Vec::new();
+        for name in verse_order {
+            let Some((_, verse_lines)) = verses.iter().find(|(n, _)| *n == name) else {
+                continue;
+            };
+
+            if lines.len() != 0 {
+                lines.push(vec![]);
+            }
+
+            let number = name
+                .trim_start_matches(|c: char| c.is_alphabetic())
+                .parse()
+                .unwrap_or(1);
+            let label = user_options.section_label(verse_name_kind(&name), number);
+
+            // a repeated section is only referenced by its label
+            if written_verses.contains(&name) {
+                if let Some(label) = label {
+                    lines.push(vec![TextNode::Label(label)]);
+                }
+
+                continue;
+            }
+
+            let mut verse_lines = verse_lines.clone();
+            if let Some(label) = label {
+                match verse_lines.first_mut() {
+                    Some(first_line) => {
+                        first_line.insert(0, TextNode::Text(" ".to_string()));
+                        first_line.insert(0, TextNode::Label(label));
+                    }
+                    None => verse_lines.push(vec![TextNode::Label(label)]),
+                }
+            }
+
+            lines.extend(verse_lines);
+            written_verses.push(name);
+        }
+
+        let mut lyrics = LyricsWithChords::new(
+            lines.join(&TextNode::Newline),
+            artist.unwrap_or_default(),
+            song_name.context("No title in the OpenLyrics file!")?,
+        );
+        lyrics.metadata = metadata;
+
+        Ok(lyrics)
+    }
+}
+
+fn write_element<W: std::io::Write>(
+    writer: &mut EventWriter<W>,
+    name: &str,
+    attributes: &[(&str, &str)],
+    value: &str,
+) -> anyhow::Result<()> {
+    let mut element = XmlEvent::start_element(name);
+    for (attribute, attribute_value) in attributes {
+        element = element.attr(*attribute, attribute_value);
+    }
+
+    writer.write(element)?;
+    writer.write(XmlEvent::characters(value))?;
+    writer.write(XmlEvent::end_element())?;
+
+    Ok(())
+}
+
+fn end_line(verses: &mut Vec<(String, Vec<Vec<TextNode>>)>, line: &mut Vec<TextNode>) {
+    // the newline in front of <br/> is not a space at the end of the line
+    if let Some(TextNode::Text(t)) = line.last_mut() {
+        *t = t.trim_end().to_string();
+        if t == "" {
+            line.pop();
+        }
+    }
+
+    if line.len() == 0 {
+        return;
+    }
+
+    if let Some((_, lines)) = verses.last_mut() {
+        lines.push(line.clone());
+    }
+
+    line.clear();
+}
+
+// https://docs.openlyrics.org/en/latest/dataformat.html#verse-name
+fn verse_name_prefix(kind: SectionKind) -> char {
+    match kind {
+        SectionKind::Verse => 'v',
+        SectionKind::Chorus | SectionKind::PostChorus => 'c',
+        SectionKind::PreChorus => 'p',
+        SectionKind::Bridge => 'b',
+        SectionKind::Intro => 'i',
+        SectionKind::Outro => 'e',
+        _ => 'o',
+    }
+}
+
+fn verse_name_kind(name: &str) -> SectionKind {
+    match name.chars().nth(0) {
+        Some('v') => SectionKind::Verse,
+        Some('c') => SectionKind::Chorus,
+        Some('p') => SectionKind::PreChorus,
+        Some('b') => SectionKind::Bridge,
+        Some('i') => SectionKind::Intro,
+        Some('e') => SectionKind::Outro,
+        _ => SectionKind::Other,
+    }
+}
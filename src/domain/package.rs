@@ -0,0 +1,30 @@
+use std::io::{Cursor, Write};
+
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+// the zip container of the epub, odt and pptx exports,
+// the files are written in the given order
+pub(crate) fn write_package(
+    mimetype: Option<&str>,
+    files: Vec<(String, Vec<u8>)>,
+) -> anyhow::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    // the mimetype has to be the first file and it must not be compressed,
+    // so it can be read at a fixed offset
+    if let Some(mimetype) = mimetype {
+        zip.start_file(
+            "mimetype",
+            FileOptions::default().compression_method(CompressionMethod::Stored),
+        )?;
+        zip.write_all(mimetype.as_bytes())?;
+    }
+
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in files {
+        zip.start_file(name, deflated)?;
+        zip.write_all(&content)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
@@ -0,0 +1,446 @@
+use printpdf::{
+    IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, PdfPageIndex,
+};
+use serde::{Deserialize, Serialize};
+use ttf_parser::Face;
+
+use super::core::{LyricsWithChords, TextNode};
+
+const PT_TO_MM: f32 = 25.4 / 72.0;
+const LINE_SPACING: f32 = 1.25;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+    pub page_width_mm: f32,
+    pub page_height_mm: f32,
+    pub margin_mm: f32,
+    // the sizes are in points
+    pub font_size: f32,
+    pub chord_font_size: f32,
+    pub title_font_size: f32,
+    // the name of the songbook, printed at the top of every page
+    pub header: Option<String>,
+    pub page_numbers: bool,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            page_width_mm: 210.0,
+            page_height_mm: 297.0,
+            margin_mm: 20.0,
+            font_size: 11.0,
+            chord_font_size: 9.0,
+            title_font_size: 14.0,
+            header: None,
+            page_numbers: true,
+        }
+    }
+}
+
+// the built-in pdf fonts can not write all of the slovak letters (ľ, ĺ, ŕ),
+// so the fonts are always embedded from truetype files
+pub struct PdfFonts {
+    pub regular: Vec<u8>,
+    // chords, labels and titles, the regular font is used when missing
+    pub bold: Option<Vec<u8>>,
+}
+
+pub struct PdfSongbook;
+
+impl PdfSongbook {
+    // every song starts on a new page
+    pub fn render(
+        songs: &[LyricsWithChords],
+        fonts: &PdfFonts,
+        options: &PdfOptions,
+    ) -> anyhow::Result<Vec<u8>> {
+        let (document, page, layer) = PdfDocument::new(
+            options.header.clone().unwrap_or("Songbook".to_string()),
+            Mm(options.page_width_mm),
+            Mm(options.page_height_mm),
+            "Songs",
+        );
+
+        let regular = Font::new(&document, &fonts.regular)?;
+        let bold = match &fonts.bold {
+            Some(bold) => Font::new(&document, bold)?,
+            None => Font::new(&document, &fonts.regular)?,
+        };
+
+        let mut writer = Writer {
+            layer: document.get_page(page).get_layer(layer),
+            document: &document,
+            page,
+            options,
+            regular: &regular,
+            bold: &bold,
+            y: 0.0,
+            pages: 1,
+            first_page: true,
+            song_title: String::new(),
+        };
+
+        for song in songs {
+            writer.write_song(song);
+        }
+
+        Ok(document.save_to_bytes()?)
+    }
+}
+
+struct Font<'a> {
+    face: Face<'a>,
+    reference: IndirectFontRef,
+}
+
+impl<'a> Font<'a> {
+    fn new(document: &PdfDocumentReference, data: &'a [u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            face: Face::parse(data, 0)?,
+            reference: document.add_external_font(data)?,
+        })
+    }
+
+    // in millimeters
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let units: u32 = text
+            .chars()
+            .map(|c| {
+                self.face
+                    .glyph_index(c)
+                    .and_then(|g| self.face.glyph_hor_advance(g))
+                    .unwrap_or(0) as u32
+            })
+            .sum();
+
+        units as f32 / self.face.units_per_em() as f32 * size * PT_TO_MM
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Lyrics,
+    Chord,
+    Label,
+}
+
+struct Item {
+    x: f32,
+    text: String,
+    style: Style,
+}
+
+// one printed line, the chords above the lyrics
+#[derive(Default)]
+struct Row {
+    items: Vec<Item>,
+}
+
+impl Row {
+    fn has_chords(&self) -> bool {
+        self.items.iter().any(|i| i.style == Style::Chord)
+    }
+
+    fn has_lyrics(&self) -> bool {
+        self.items
+            .iter()
+            .any(|i| i.style != Style::Chord && i.text.trim() != "")
+    }
+}
+
+// a piece of a line which is never split, a word with the chord above it
+struct Unit {
+    chord: Option<String>,
+    text: String,
+    style: Style,
+}
+
+struct Writer<'a, 'f> {
+    document: &'a PdfDocumentReference,
+    page: PdfPageIndex,
+    layer: PdfLayerReference,
+    options: &'a PdfOptions,
+    regular: &'a Font<'f>,
+    bold: &'a Font<'f>,
+    // the baseline of the next row, from the bottom of the page
+    y: f32,
+    pages: usize,
+    first_page: bool,
+    song_title: String,
+}
+
+impl<'a, 'f> Writer<'a, 'f> {
+    fn line_height(&self, size: f32) -> f32 {
+        size * LINE_SPACING * PT_TO_MM
+    }
+
+    fn row_height(&self, row: &Row) -> f32 {
+        let mut height = 0.0;
+
+        if row.has_chords() {
+            height += self.line_height(self.options.chord_font_size);
+        }
+
+        if row.has_lyrics() || !row.has_chords() {
+            height += self.line_height(self.options.font_size);
+        }
+
+        height
+    }
+
+    fn top(&self) -> f32 {
+        self.options.page_height_mm - self.options.margin_mm
+    }
+
+    fn bottom(&self) -> f32 {
+        self.options.margin_mm
+    }
+
+    fn new_page(&mut self) {
+        // the first page is created together with the document
+        if self.first_page {
+            self.first_page = false;
+        } else {
+            let (page, layer) = self.document.add_page(
+                Mm(self.options.page_width_mm),
+                Mm(self.options.page_height_mm),
+                "Songs",
+            );
+            self.page = page;
+            self.layer = self.document.get_page(page).get_layer(layer);
+            self.pages += 1;
+        }
+
+        self.y = self.top();
+        self.write_header();
+    }
+
+    fn write_header(&self) {
+        let size = self.options.chord_font_size;
+        let header_y = self.options.page_height_mm - self.options.margin_mm / 2.0;
+        let footer_y = self.options.margin_mm / 2.0;
+        let right = self.options.page_width_mm - self.options.margin_mm;
+
+        if let Some(header) = &self.options.header {
+            self.text(header, size, self.options.margin_mm, header_y, self.regular);
+        }
+
+        // the song title helps when a song continues on the next page
+        let title_x = right - self.regular.width(&self.song_title, size);
+        self.text(&self.song_title, size, title_x, header_y, self.regular);
+
+        if self.options.page_numbers {
+            let number = self.pages.to_string();
+            let number_x = (self.options.page_width_mm - self.regular.width(&number, size)) / 2.0;
+            self.text(&number, size, number_x, footer_y, self.regular);
+        }
+    }
+
+    fn text(&self, text: &str, size: f32, x: f32, y: f32, font: &Font) {
+        if text == "" {
+            return;
+        }
+
+        self.layer
+            .use_text(text, size, Mm(x), Mm(y), &font.reference);
+    }
+
+    fn write_song(&mut self, song: &LyricsWithChords) {
+        self.song_title = if song.artist == "" {
+            song.song_name.clone()
+        } else {
+            format!("{} - {}", song.artist, song.song_name)
+        };
+
+        self.new_page();
+        self.document
+            .add_bookmark(self.song_title.clone(), self.page);
+
+        let title_size = self.options.title_font_size;
+        self.y -= self.line_height(title_size);
+        self.text(
+            &self.song_title,
+            title_size,
+            self.options.margin_mm,
+            self.y,
+            self.bold,
+        );
+        self.y -= self.line_height(self.options.font_size);
+
+        // the parts between blank lines are kept on one page when they fit
+        let lines: Vec<&[TextNode]> = song.text.split(|n| n == &TextNode::Newline).collect();
+        for block in lines.split(|line| line.len() == 0) {
+            if block.len() == 0 {
+                continue;
+            }
+
+            let rows: Vec<Row> = block.iter().flat_map(|line| self.layout(line)).collect();
+
+            let height: f32 = rows.iter().map(|r| self.row_height(r)).sum();
+            let page_height = self.top() - self.bottom();
+            if self.y - height < self.bottom() && height <= page_height {
+                self.new_page();
+            }
+
+            for row in rows {
+                self.write_row(&row);
+            }
+
+            if self.y != self.top() {
+                self.y -= self.line_height(self.options.font_size);
+            }
+        }
+    }
+
+    fn write_row(&mut self, row: &Row) {
+        if self.y - self.row_height(row) < self.bottom() {
+            self.new_page();
+        }
+
+        if row.has_chords() {
+            self.y -= self.line_height(self.options.chord_font_size);
+
+            for item in row.items.iter().filter(|i| i.style == Style::Chord) {
+                let x = self.options.margin_mm + item.x;
+                self.text(
+                    &item.text,
+                    self.options.chord_font_size,
+                    x,
+                    self.y,
+                    self.bold,
+                );
+            }
+        }
+
+        if row.has_lyrics() || !row.has_chords() {
+            self.y -= self.line_height(self.options.font_size);
+
+            for item in row.items.iter().filter(|i| i.style != Style::Chord) {
+                let font = match item.style {
+                    Style::Label => self.bold,
+                    _ => self.regular,
+                };
+
+                let x = self.options.margin_mm + item.x;
+                self.text(&item.text, self.options.font_size, x, self.y, font);
+            }
+        }
+    }
+
+    fn units(&self, line: &[TextNode]) -> Vec<Unit> {
+        let mut units = Vec::new();
+        let mut chord = None;
+
+        for node in line {
+            match node {
+                TextNode::Label(l) => units.push(Unit {
+                    chord: None,
+                    text: l.clone() + " ",
+                    style: Style::Label,
+                }),
+                TextNode::Chord(ch) => {
+                    // two chords in a row
+                    if let Some(previous) = chord.replace(ch.clone()) {
+                        units.push(Unit {
+                            chord: Some(previous),
+                            text: String::new(),
+                            style: Style::Lyrics,
+                        });
+                    }
+                }
+                TextNode::Text(t) => {
+                    for word in t.split_inclusive(" ") {
+                        units.push(Unit {
+                            chord: chord.take(),
+                            text: word.to_string(),
+                            style: Style::Lyrics,
+                        });
+                    }
+                }
+                TextNode::Newline => {}
+            }
+        }
+
+        if let Some(chord) = chord {
+            units.push(Unit {
+                chord: Some(chord),
+                text: String::new(),
+                style: Style::Lyrics,
+            });
+        }
+
+        units
+    }
+
+    // lines too long for the page are wrapped between words
+    fn layout(&self, line: &[TextNode]) -> Vec<Row> {
+        let max_width = self.options.page_width_mm - 2.0 * self.options.margin_mm;
+        let font_size = self.options.font_size;
+        let chord_size = self.options.chord_font_size;
+        let chord_gap = self.bold.width(" ", chord_size);
+
+        let units = self.units(line);
+
+        let mut rows = vec![Row::default()];
+        let mut x: f32 = 0.0;
+        let mut chord_end: f32 = 0.0;
+        for (i, unit) in units.iter().enumerate() {
+            let font = match unit.style {
+                Style::Label => self.bold,
+                _ => self.regular,
+            };
+
+            let text_width = font.width(&unit.text, font_size);
+            let chord_width = unit
+                .chord
+                .as_ref()
+                .map(|ch| self.bold.width(ch, chord_size) + chord_gap)
+                .unwrap_or(0.0);
+
+            let can_break = i != 0 && units[i - 1].text.ends_with(" ");
+            if can_break && x > 0.0 && x + text_width.max(chord_width) > max_width {
+                rows.push(Row::default());
+                x = 0.0;
+                chord_end = 0.0;
+            }
+
+            let row = rows.last_mut().unwrap();
+
+            if let Some(chord) = &unit.chord {
+                // the lyrics make room for chords which would overlap
+                x = x.max(chord_end);
+                chord_end = x + chord_width;
+
+                row.items.push(Item {
+                    x,
+                    text: chord.clone(),
+                    style: Style::Chord,
+                });
+            }
+
+            let text = if x == 0.0 {
+                unit.text.trim_start().to_string()
+            } else {
+                unit.text.clone()
+            };
+
+            let width = font.width(&text, font_size);
+            match row.items.last_mut() {
+                // the words between two chords are written at once
+                Some(item) if unit.chord.is_none() && item.style == unit.style => {
+                    item.text += &text
+                }
+                _ => row.items.push(Item {
+                    x,
+                    text,
+                    style: unit.style,
+                }),
+            }
+            x += width;
+        }
+
+        rows
+    }
+}
@@ -0,0 +1,504 @@
+use nom::{
+    bytes::complete::{take_while, take_while_m_n},
+    character::complete::{char, one_of},
+    combinator::{all_consuming, opt, recognize, verify},
+    error::ErrorKind,
+    sequence::{pair, preceded, tuple},
+    IResult,
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::{
+    alignment::{chord_columns, is_chord_line, is_filler, merge_chord_line, AlignmentGuess},
+    core::{LyricsWithChords, Options, SectionCounter, SectionKind, TextNode},
+    supermusic::section_marker,
+};
+
+// chord sheets pasted from e-mails and forums, chords are written
+// on their own line above the lyrics they belong to
+pub struct PlainText;
+
+impl PlainText {
+    pub fn parse(input: &str, options: Option<Options>) -> anyhow::Result<LyricsWithChords> {
+        Ok(Self::parse_with_guesses(input, options)?.0)
+    }
+
+    pub fn parse_with_guesses(
+        input: &str,
+        options: Option<Options>,
+    ) -> anyhow::Result<(LyricsWithChords, Vec<(usize, AlignmentGuess)>)> {
+        let user_options = options.unwrap_or_default();
+
+        let lines: Vec<String> = input
+            .replace("\r\n", "\n")
+            .split("\n")
+            .map(|l| expand_tabs(l.trim_end()))
+            .collect();
+
+        let mut lines = lines.as_slice();
+        while lines.first().is_some_and(|l| l.trim() == "") {
+            lines = &lines[1..];
+        }
+
+        // "Artist - Title" followed by a blank line
+        let (artist, song_name) = match lines {
+            [first, second, ..]
+                if second.trim() == ""
+                    && chord_line(first).is_none()
+                    && heading(first).is_none() =>
+            {
+                lines = &lines[2..];
+
+                match first.trim().split_once(" - ") {
+                    Some((artist, song_name)) => (artist.trim(), song_name.trim()),
+                    None => ("", first.trim()),
+                }
+            }
+            _ => ("", ""),
+        };
+
+        let mut section_counter = SectionCounter::default();
+        let mut guesses = Vec::new();
+        let mut merged_lines: Vec<Vec<TextNode>> = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = &lines[i];
+            i += 1;
+
+            if line.trim() == "" {
+                if matches!(merged_lines.last(), Some(l) if l.len() != 0) {
+                    merged_lines.push(vec![]);
+                }
+
+                continue;
+            }
+
+            // "[C] [G]", chords which are not above any lyrics
+            if let Some(chords) = bracketed_chord_line(line) {
+                merged_lines.push(chord_streak(&chords));
+                continue;
+            }
+
+            if let Some(chords) = chord_line(line) {
+                let lyrics = lines
+                    .get(i)
+                    .filter(|l| l.trim() != "" && chord_line(l).is_none() && heading(l).is_none());
+
+                let Some(lyrics) = lyrics else {
+                    // chords without lyrics under them (intros, instrumental parts)
+                    // stay a streak of chords
+                    merged_lines.push(chord_streak(&chords));
+                    continue;
+                };
+                i += 1;
+
+                // the marker is blanked out, so the chords keep their columns
+                let (label, lyrics) = match marker(lyrics) {
+                    Some((written, kind, number, rest)) => (
+                        section_counter.relabel(&user_options, kind, number, written),
+                        " ".repeat(written.width()) + rest,
+                    ),
+                    None => (None, lyrics.clone()),
+                };
+
+                if label.is_some() {
+                    start_section(&mut merged_lines);
+                }
+
+                let columns = chord_columns(&chords);
+                let alignment = merge_chord_line(&columns, &lyrics);
+                let mut line = strip_padding(alignment.line, &columns);
+                if let Some(label) = label {
+                    if let Some(TextNode::Text(t)) = line.first_mut() {
+                        *t = t.trim_start().to_string();
+                    }
+
+                    line.insert(0, TextNode::Text(" ".to_string()));
+                    line.insert(0, TextNode::Label(label));
+                }
+
+                guesses.extend(
+                    alignment
+                        .guesses
+                        .into_iter()
+                        .map(|guess| (merged_lines.len(), guess)),
+                );
+                merged_lines.push(line);
+                continue;
+            }
+
+            if let Some(heading) = heading(line) {
+                start_section(&mut merged_lines);
+
+                let label = match heading {
+                    (SectionKind::Other, _, name) => Some(name),
+                    (kind, number, name) => {
+                        section_counter.relabel(&user_options, kind, number, &name)
+                    }
+                };
+
+                if let Some(label) = label {
+                    merged_lines.push(vec![TextNode::Label(label)]);
+                }
+
+                continue;
+            }
+
+            match marker(line) {
+                Some((written, kind, number, rest)) => {
+                    start_section(&mut merged_lines);
+
+                    // "Intro: C G | Am F"
+                    let rest = match chord_line(rest) {
+                        Some(chords) => chord_streak(&chords),
+                        None => vec![TextNode::Text(rest.trim().to_string())],
+                    };
+                    let label = section_counter.relabel(&user_options, kind, number, written);
+                    merged_lines.push(match label {
+                        Some(label) => [
+                            vec![TextNode::Label(label), TextNode::Text(" ".to_string())],
+                            rest,
+                        ]
+                        .concat(),
+                        None => rest,
+                    });
+                }
+                None => merged_lines.push(vec![TextNode::Text(line.trim().to_string())]),
+            }
+        }
+
+        while matches!(merged_lines.last(), Some(l) if l.len() == 0) {
+            merged_lines.pop();
+        }
+
+        Ok((
+            LyricsWithChords::new(
+                merged_lines.join(&TextNode::Newline),
+                artist.to_string(),
+                song_name.to_string(),
+            ),
+            guesses,
+        ))
+    }
+
+    // the inverse of `parse`, every line becomes a row of chords above a row of lyrics
+    pub fn render(song: &LyricsWithChords) -> String {
+        let mut output = Vec::new();
+
+        let title = match (song.artist.as_str(), song.song_name.as_str()) {
+            ("", song_name) => song_name.to_string(),
+            (artist, song_name) => format!("{} - {}", artist, song_name),
+        };
+        if title != "" {
+            output.push(title);
+            output.push(String::new());
+        }
+
+        let lines: Vec<&[TextNode]> = song.text.split(|n| n == &TextNode::Newline).collect();
+        for (i, line) in lines.iter().enumerate() {
+            let line = *line;
+            match line {
+                // a label alone on its line is written as a heading
+                [TextNode::Label(label), rest @ ..]
+                    if rest
+                        .iter()
+                        .all(|n| matches!(n, TextNode::Text(t) if t.trim() == "")) =>
+                {
+                    output.push(format!("[{}]", label.trim()));
+                }
+                // the bar lines stay with the chords, the chords are put in brackets
+                // when a line without chords follows, they would be read as its chords
+                _ if is_chord_line(line) => {
+                    let bracketed = lines.get(i + 1).is_some_and(|next| {
+                        !next.iter().any(|n| matches!(n, TextNode::Chord(_)))
+                            && render_line(next).1.trim() != ""
+                    });
+
+                    let chords: String = line
+                        .iter()
+                        .map(|n| match n {
+                            TextNode::Chord(ch) if bracketed => format!("[{}]", ch),
+                            TextNode::Chord(t) | TextNode::Text(t) => t.clone(),
+                            _ => String::new(),
+                        })
+                        .collect();
+                    output.push(chords.trim_end().to_string());
+                }
+                _ => {
+                    let (chords, lyrics) = render_line(line);
+
+                    if chords.trim() != "" {
+                        output.push(chords.trim_end().to_string());
+                    }
+                    if lyrics.trim() != "" || chords.trim() == "" {
+                        output.push(lyrics.trim_end().to_string());
+                    }
+                }
+            }
+        }
+
+        output.join("\n") + "\n"
+    }
+}
+
+// the chord row and the lyrics row of one line, the columns are counted
+// in the display width, so wide characters keep the chords aligned
+fn render_line(line: &[TextNode]) -> (String, String) {
+    let mut chords = String::new();
+    let mut lyrics = String::new();
+
+    for node in line {
+        match node {
+            TextNode::Text(t) | TextNode::Label(t) => lyrics += t,
+            TextNode::Chord(ch) => {
+                let column = lyrics.width();
+
+                // a chord longer than its syllable pushes the lyrics to the right with spaces,
+                // `strip_padding` tells them from the lyrics by the gap between the chords,
+                // two columns after a syllable and one column after a space
+                if chords != "" && chords.width() >= column {
+                    let gap = if lyrics.ends_with(char::is_whitespace) {
+                        1
+                    } else {
+                        2
+                    };
+
+                    lyrics += &" ".repeat(chords.width() + gap - column);
+                }
+
+                let column = lyrics.width();
+                chords += &" ".repeat(column - chords.width());
+                chords += ch;
+            }
+            TextNode::Newline => {}
+        }
+    }
+
+    (chords, lyrics)
+}
+
+// the spaces which `render_line` put in front of the chords which did not fit,
+// two or more spaces in front of a chord close to the previous one
+fn strip_padding(line: Vec<TextNode>, columns: &[(usize, String)]) -> Vec<TextNode> {
+    let gaps: Vec<Option<usize>> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (column, _))| match i {
+            0 => None,
+            _ => {
+                let (previous, chord) = &columns[i - 1];
+                column.checked_sub(previous + chord.width())
+            }
+        })
+        .collect();
+
+    let mut stripped: Vec<TextNode> = Vec::new();
+    let mut chord_index = 0;
+    for node in line {
+        if let TextNode::Chord(_) = node {
+            let gap = gaps.get(chord_index).copied().flatten();
+            chord_index += 1;
+
+            if let (Some(gap @ (1 | 2)), Some(TextNode::Text(t))) = (gap, stripped.last_mut()) {
+                let text = t.trim_end_matches(' ');
+
+                if t.len() - text.len() >= 2 {
+                    *t = match gap {
+                        // the spaces between two chords
+                        _ if text.trim() == "" => " ".to_string(),
+                        1 => format!("{} ", text),
+                        _ => text.to_string(),
+                    };
+                }
+
+                if t == "" {
+                    stripped.pop();
+                }
+            }
+        }
+
+        stripped.push(node);
+    }
+
+    stripped
+}
+
+// the chords and bar lines of a chord line, one space apart
+fn chord_streak(chords: &[TextNode]) -> Vec<TextNode> {
+    chords
+        .iter()
+        .filter(|n| !matches!(n, TextNode::Text(t) if t.trim() == ""))
+        .enumerate()
+        .flat_map(|(i, n)| match i {
+            0 => vec![n.clone()],
+            _ => vec![TextNode::Text(" ".to_string()), n.clone()],
+        })
+        .collect()
+}
+
+fn start_section(lines: &mut Vec<Vec<TextNode>>) {
+    if matches!(lines.last(), Some(l) if l.len() != 0) {
+        lines.push(vec![]);
+    }
+}
+
+// columns only make sense with the tabs turned into spaces
+fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::new();
+
+    for c in line.chars() {
+        if c == '\t' {
+            let width = expanded.width();
+            expanded += &" ".repeat(8 - width % 8);
+        } else {
+            expanded.push(c);
+        }
+    }
+
+    expanded
+}
+
+// a line made only of chords and bar lines, kept with its spacing
+fn chord_line(line: &str) -> Option<Vec<TextNode>> {
+    let mut nodes = Vec::new();
+    let mut has_chord = false;
+
+    let mut rest = line;
+    while rest.len() != 0 {
+        let token_start = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        if token_start != 0 {
+            nodes.push(TextNode::Text(rest[..token_start].to_string()));
+            rest = &rest[token_start..];
+            continue;
+        }
+
+        let token_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let token = &rest[..token_end];
+        rest = &rest[token_end..];
+
+        if is_chord(token) {
+            has_chord = true;
+            nodes.push(TextNode::Chord(token.to_string()));
+        } else if is_filler(token) {
+            nodes.push(TextNode::Text(token.to_string()));
+        } else {
+            return None;
+        }
+    }
+
+    if !has_chord {
+        return None;
+    }
+
+    Some(nodes)
+}
+
+// a chord line with the chords in brackets
+fn bracketed_chord_line(line: &str) -> Option<Vec<TextNode>> {
+    let mut nodes = Vec::new();
+
+    for token in line.split_whitespace() {
+        if nodes.len() != 0 {
+            nodes.push(TextNode::Text(" ".to_string()));
+        }
+
+        match token.strip_prefix("[").and_then(|t| t.strip_suffix("]")) {
+            Some(chord) if is_chord(chord) => nodes.push(TextNode::Chord(chord.to_string())),
+            _ if is_filler(token) => nodes.push(TextNode::Text(token.to_string())),
+            _ => return None,
+        }
+    }
+
+    nodes
+        .iter()
+        .any(|n| matches!(n, TextNode::Chord(_)))
+        .then_some(nodes)
+}
+
+fn is_chord(token: &str) -> bool {
+    all_consuming(chord_name::<(&str, ErrorKind)>)(token).is_ok()
+}
+
+fn note<'a, E: nom::error::ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(pair(one_of("ABCDEFGH"), opt(one_of("#b"))))(i)
+}
+
+// "C", "F#m7", "Bbmaj7/D", "Dsus4", "E7(b9)", "Am/G"
+fn chord_name<'a, E: nom::error::ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(tuple((
+        note,
+        verify(
+            take_while(|c: char| c.is_ascii_alphanumeric() || "#+-()°øΔ".contains(c)),
+            |quality: &str| is_chord_quality(quality),
+        ),
+        opt(preceded(char('/'), note)),
+    )))(i)
+}
+
+fn is_chord_quality(quality: &str) -> bool {
+    let parts = [
+        "maj", "min", "dim", "aug", "sus", "add", "m", "M", "#", "b", "+", "-", "(", ")", "°", "ø",
+        "Δ",
+    ];
+
+    let mut rest = quality;
+    while rest.len() != 0 {
+        if let Some(part) = parts.iter().find(|p| rest.starts_with(*p)) {
+            rest = &rest[part.len()..];
+        } else if let Ok((r, _)) =
+            take_while_m_n::<_, _, (&str, ErrorKind)>(1, 2, |c: char| c.is_ascii_digit())(rest)
+        {
+            rest = r;
+        } else {
+            return false;
+        }
+    }
+
+    true
+}
+
+// "[Verse 1]", "Chorus:", "Bridge", "R:", a line which only names the section
+fn heading(line: &str) -> Option<(SectionKind, Option<usize>, String)> {
+    let line = line.trim();
+
+    // "R:", "2." alone on the line
+    if let Ok((rest, (kind, number))) = section_marker::<(&str, ErrorKind)>(line) {
+        if rest.trim() == "" {
+            return Some((kind, number, line.to_string()));
+        }
+    }
+
+    let name = match line.strip_prefix("[").and_then(|l| l.strip_suffix("]")) {
+        Some(name) => name.trim(),
+        None => line.strip_suffix(":").unwrap_or(line).trim(),
+    };
+
+    if name == "" || name.split_whitespace().count() > 3 {
+        return None;
+    }
+
+    // without the brackets, only "Chorus", "Verse 2:" and such are headings,
+    // "And she said:" is a line of the lyrics
+    if !line.starts_with("[") && SectionKind::from_name(name).is_none() {
+        return None;
+    }
+
+    let (kind, number) = SectionKind::from_label(name);
+
+    Some((kind, number, name.to_string()))
+}
+
+// "R: lyrics", "2. lyrics", the marker as written (with the whitespace
+// in front of it) and the lyrics after it
+fn marker(line: &str) -> Option<(&str, SectionKind, Option<usize>, &str)> {
+    let (rest, (kind, number)) = section_marker::<(&str, ErrorKind)>(line.trim_start()).ok()?;
+
+    // a marker alone on its line is a heading
+    if rest.trim() == "" {
+        return None;
+    }
+
+    Some((&line[..line.len() - rest.len()], kind, number, rest))
+}
@@ -0,0 +1,352 @@
+use html_escape::encode_text;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    core::{LyricsWithChords, Options, TextNode},
+    package::write_package,
+};
+
+// 16:9 in EMU, the unit of office drawings
+const SLIDE_WIDTH: u64 = 12192000;
+const SLIDE_HEIGHT: u64 = 6858000;
+const SLIDE_MARGIN: u64 = 457200;
+
+const PRESENTATION_NS: &str = r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main""#;
+
+const ROOT_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="ppt/presentation.xml"/>
+</Relationships>
+"#;
+
+const MASTER_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="../theme/theme1.xml"/>
+</Relationships>
+"#;
+
+const LAYOUT_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideMaster" Target="../slideMasters/slideMaster1.xml"/>
+</Relationships>
+"#;
+
+const SLIDE_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
+</Relationships>
+"#;
+
+// white text on black, the best for the projector
+const THEME: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<a:theme xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" name="Songs">
+<a:themeElements>
+<a:clrScheme name="Songs">
+<a:dk1><a:srgbClr val="000000"/></a:dk1>
+<a:lt1><a:srgbClr val="FFFFFF"/></a:lt1>
+<a:dk2><a:srgbClr val="1F1F1F"/></a:dk2>
+<a:lt2><a:srgbClr val="E7E6E6"/></a:lt2>
+<a:accent1><a:srgbClr val="4472C4"/></a:accent1>
+<a:accent2><a:srgbClr val="ED7D31"/></a:accent2>
+<a:accent3><a:srgbClr val="A5A5A5"/></a:accent3>
+<a:accent4><a:srgbClr val="FFC000"/></a:accent4>
+<a:accent5><a:srgbClr val="5B9BD5"/></a:accent5>
+<a:accent6><a:srgbClr val="70AD47"/></a:accent6>
+<a:hlink><a:srgbClr val="0563C1"/></a:hlink>
+<a:folHlink><a:srgbClr val="954F72"/></a:folHlink>
+</a:clrScheme>
+<a:fontScheme name="Songs">
+<a:majorFont><a:latin typeface="Arial"/><a:ea typeface=""/><a:cs typeface=""/></a:majorFont>
+<a:minorFont><a:latin typeface="Arial"/><a:ea typeface=""/><a:cs typeface=""/></a:minorFont>
+</a:fontScheme>
+<a:fmtScheme name="Songs">
+<a:fillStyleLst>
+<a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+<a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+<a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+</a:fillStyleLst>
+<a:lnStyleLst>
+<a:ln w="6350"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln>
+<a:ln w="12700"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln>
+<a:ln w="19050"><a:solidFill><a:schemeClr val="phClr"/></a:solidFill></a:ln>
+</a:lnStyleLst>
+<a:effectStyleLst>
+<a:effectStyle><a:effectLst/></a:effectStyle>
+<a:effectStyle><a:effectLst/></a:effectStyle>
+<a:effectStyle><a:effectLst/></a:effectStyle>
+</a:effectStyleLst>
+<a:bgFillStyleLst>
+<a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+<a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+<a:solidFill><a:schemeClr val="phClr"/></a:solidFill>
+</a:bgFillStyleLst>
+</a:fmtScheme>
+</a:themeElements>
+</a:theme>
+"#;
+
+const EMPTY_TREE: &str =
+    r#"<p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr/>"#;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PptxOptions {
+    // longer sections are split into more slides
+    pub lines_per_slide: usize,
+    // the sizes are in points
+    pub font_size: u32,
+    pub title_font_size: u32,
+}
+
+impl Default for PptxOptions {
+    fn default() -> Self {
+        Self {
+            lines_per_slide: 6,
+            font_size: 40,
+            title_font_size: 54,
+        }
+    }
+}
+
+// a presentation for projecting the lyrics, a title slide
+// for every song followed by the slides of its sections
+pub struct Pptx;
+
+impl Pptx {
+    pub fn render(
+        songs: &[LyricsWithChords],
+        options: Option<Options>,
+        pptx_options: &PptxOptions,
+    ) -> anyhow::Result<Vec<u8>> {
+        let user_options = options.unwrap_or_default();
+
+        let mut slides = Vec::new();
+        for song in songs {
+            slides.push(title_slide(song, pptx_options));
+
+            for lines in song_slides(song, &user_options, pptx_options.lines_per_slide) {
+                slides.push(slide(&text_body(
+                    &lines
+                        .iter()
+                        .map(|l| (l.as_str(), pptx_options.font_size))
+                        .collect::<Vec<_>>(),
+                )));
+            }
+        }
+
+        let mut files: Vec<(String, String)> = vec![
+            (
+                "[Content_Types].xml".to_string(),
+                content_types(slides.len()),
+            ),
+            ("_rels/.rels".to_string(), ROOT_RELATIONSHIPS.to_string()),
+            (
+                "ppt/presentation.xml".to_string(),
+                presentation(slides.len()),
+            ),
+            (
+                "ppt/_rels/presentation.xml.rels".to_string(),
+                presentation_relationships(slides.len()),
+            ),
+            ("ppt/slideMasters/slideMaster1.xml".to_string(), master()),
+            (
+                "ppt/slideMasters/_rels/slideMaster1.xml.rels".to_string(),
+                MASTER_RELATIONSHIPS.to_string(),
+            ),
+            ("ppt/slideLayouts/slideLayout1.xml".to_string(), layout()),
+            (
+                "ppt/slideLayouts/_rels/slideLayout1.xml.rels".to_string(),
+                LAYOUT_RELATIONSHIPS.to_string(),
+            ),
+            ("ppt/theme/theme1.xml".to_string(), THEME.to_string()),
+        ];
+
+        for (i, slide) in slides.into_iter().enumerate() {
+            files.push((format!("ppt/slides/slide{}.xml", i + 1), slide));
+            files.push((
+                format!("ppt/slides/_rels/slide{}.xml.rels", i + 1),
+                SLIDE_RELATIONSHIPS.to_string(),
+            ));
+        }
+
+        write_package(
+            None,
+            files
+                .into_iter()
+                .map(|(name, content)| (name, content.into_bytes()))
+                .collect(),
+        )
+    }
+}
+
+// the lines of every slide, the chords are not projected
+fn song_slides(
+    song: &LyricsWithChords,
+    options: &Options,
+    lines_per_slide: usize,
+) -> Vec<Vec<String>> {
+    let sections = song.without_chords().sections(options);
+    let lines_per_slide = lines_per_slide.max(1);
+
+    let mut slides = Vec::new();
+    for (i, section) in sections.iter().enumerate() {
+        // a repeated section is projected again in full
+        let lines = match section.lines.len() {
+            0 => sections[..i]
+                .iter()
+                .rev()
+                .find(|s| s.label == section.label && s.lines.len() != 0)
+                .map(|s| &s.lines),
+            _ => Some(&section.lines),
+        };
+        let Some(lines) = lines else {
+            continue;
+        };
+
+        let lines: Vec<String> = lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|n| match n {
+                        TextNode::Text(t) | TextNode::Label(t) => t.as_str(),
+                        _ => "",
+                    })
+                    .collect::<String>()
+                    .trim()
+                    .to_string()
+            })
+            .collect();
+
+        // the lines are spread evenly, so no slide is left with a single line
+        let count = (lines.len() + lines_per_slide - 1) / lines_per_slide;
+        let size = (lines.len() + count - 1) / count;
+        slides.extend(lines.chunks(size).map(|chunk| chunk.to_vec()));
+    }
+
+    slides
+}
+
+fn title_slide(song: &LyricsWithChords, pptx_options: &PptxOptions) -> String {
+    let mut paragraphs = vec![(song.song_name.as_str(), pptx_options.title_font_size)];
+    if song.artist != "" {
+        paragraphs.push((song.artist.as_str(), pptx_options.font_size));
+    }
+
+    slide(&text_body(&paragraphs))
+}
+
+fn text_body(paragraphs: &[(&str, u32)]) -> String {
+    let paragraphs: String = paragraphs
+        .iter()
+        .map(|(text, size)| {
+            format!(
+                r#"<a:p><a:pPr algn="ctr"/><a:r><a:rPr lang="sk-SK" sz="{}" dirty="0"><a:solidFill><a:schemeClr val="tx1"/></a:solidFill></a:rPr><a:t>{}</a:t></a:r></a:p>"#,
+                size * 100,
+                encode_text(text)
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<p:txBody><a:bodyPr wrap="square" anchor="ctr"><a:normAutofit/></a:bodyPr><a:lstStyle/>{}</p:txBody>"#,
+        paragraphs
+    )
+}
+
+fn slide(text_body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:sld {}><p:cSld><p:spTree>{}<p:sp><p:nvSpPr><p:cNvPr id="2" name="Lyrics"/><p:cNvSpPr txBox="1"/><p:nvPr/></p:nvSpPr><p:spPr><a:xfrm><a:off x="{}" y="{}"/><a:ext cx="{}" cy="{}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom><a:noFill/></p:spPr>{}</p:sp></p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sld>
+"#,
+        PRESENTATION_NS,
+        EMPTY_TREE,
+        SLIDE_MARGIN,
+        SLIDE_MARGIN,
+        SLIDE_WIDTH - 2 * SLIDE_MARGIN,
+        SLIDE_HEIGHT - 2 * SLIDE_MARGIN,
+        text_body
+    )
+}
+
+fn master() -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:sldMaster {}><p:cSld><p:bg><p:bgRef idx="1001"><a:schemeClr val="bg1"/></p:bgRef></p:bg><p:spTree>{}</p:spTree></p:cSld><p:clrMap bg1="dk1" tx1="lt1" bg2="dk2" tx2="lt2" accent1="accent1" accent2="accent2" accent3="accent3" accent4="accent4" accent5="accent5" accent6="accent6" hlink="hlink" folHlink="folHlink"/><p:sldLayoutIdLst><p:sldLayoutId id="2147483649" r:id="rId1"/></p:sldLayoutIdLst></p:sldMaster>
+"#,
+        PRESENTATION_NS, EMPTY_TREE
+    )
+}
+
+fn layout() -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:sldLayout {} preserve="1"><p:cSld name="Blank"><p:spTree>{}</p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sldLayout>
+"#,
+        PRESENTATION_NS, EMPTY_TREE
+    )
+}
+
+fn presentation(slide_count: usize) -> String {
+    // the first relationship is the master, the slides follow it
+    let slide_ids: String = (0..slide_count)
+        .map(|i| format!(r#"<p:sldId id="{}" r:id="rId{}"/>"#, 256 + i, i + 2))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:presentation {}><p:sldMasterIdLst><p:sldMasterId id="2147483648" r:id="rId1"/></p:sldMasterIdLst><p:sldIdLst>{}</p:sldIdLst><p:sldSz cx="{}" cy="{}"/><p:notesSz cx="{}" cy="{}"/></p:presentation>
+"#,
+        PRESENTATION_NS, slide_ids, SLIDE_WIDTH, SLIDE_HEIGHT, SLIDE_HEIGHT, SLIDE_WIDTH
+    )
+}
+
+fn presentation_relationships(slide_count: usize) -> String {
+    let slides: String = (0..slide_count)
+        .map(|i| {
+            format!(
+                r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide" Target="slides/slide{}.xml"/>"#,
+                i + 2,
+                i + 1
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideMaster" Target="slideMasters/slideMaster1.xml"/>
+{}
+<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="theme/theme1.xml"/>
+</Relationships>
+"#,
+        slides,
+        slide_count + 2
+    )
+}
+
+fn content_types(slide_count: usize) -> String {
+    let slides: String = (0..slide_count)
+        .map(|i| {
+            format!(
+                r#"<Override PartName="/ppt/slides/slide{}.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/>"#,
+                i + 1
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Override PartName="/ppt/presentation.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.presentation.main+xml"/>
+<Override PartName="/ppt/slideMasters/slideMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideMaster+xml"/>
+<Override PartName="/ppt/slideLayouts/slideLayout1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideLayout+xml"/>
+<Override PartName="/ppt/theme/theme1.xml" ContentType="application/vnd.openxmlformats-officedocument.theme+xml"/>
+{}
+</Types>
+"#,
+        slides
+    )
+}
@@ -4,25 +4,26 @@ use anyhow::Context;
 use itertools::Itertools;
 use nom::{
     branch::alt,
-    bytes::complete::{take_while1, take_while_m_n},
-    character::complete::char,
-    combinator::map,
+    bytes::complete::{tag, tag_no_case, take_while1, take_while_m_n},
+    character::complete::{char, digit0, digit1, multispace1, one_of},
+    combinator::{eof, map, map_opt, opt, peek},
     error::{context, ContextError, ErrorKind, ParseError},
-    sequence::delimited,
+    sequence::{delimited, pair, terminated},
     IResult,
 };
-use reqwest::Client;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Node, Selector};
 
-use super::core::{Appendable, LyricsWithChords, TextNode};
+use crate::network::Network;
+
+use super::core::{
+    collapse_newlines, Appendable, LyricsWithChords, Options, SectionCounter, SectionKind,
+    SongMetadata, TextNode,
+};
 
 pub struct Supermusic {}
 
 impl Supermusic {
-    pub fn get(
-        document: &scraper::Html,
-        txt_export_document: String,
-    ) -> anyhow::Result<super::core::LyricsWithChords> {
+    fn get_title(document: &Html) -> anyhow::Result<(String, String)> {
         let song_name_selector = Selector::parse(".test3").map_err(|_| {
             io::Error::new(io::ErrorKind::InvalidData, "Failed to create selector!")
         })?;
@@ -32,16 +33,85 @@ impl Supermusic {
             .nth(0)
             .context("Unexpected document structure!")?
             .text()
-            .nth(0)
-            .context("No song name in DOM!")?;
+            .collect::<String>();
 
-        let [artist, song_name] = whole_song_title.split(" - ").collect::<Vec<&str>>()[0..2] else {
+        // only the first " - " separates the artist,
+        // the rest belongs to the song name ("Artist - Song - Live")
+        let Some((artist, song_name)) = whole_song_title.trim().split_once(" - ") else {
             return Err(anyhow::Error::msg("Unexpected structure of song title"));
         };
 
+        Ok((artist.trim().to_string(), song_name.trim().to_string()))
+    }
+
+    // the metadata are in the table with the title of the song,
+    // the rest of the page lists other songs which may mention a capo or an album too
+    fn get_metadata(document: &Html) -> SongMetadata {
+        let Ok(title_selector) = Selector::parse(".test3") else {
+            return SongMetadata::default();
+        };
+        let Some(title) = document.select(&title_selector).nth(0) else {
+            return SongMetadata::default();
+        };
+
+        let metadata_element = title
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .find(|elem| elem.value().name() == "table")
+            .or(title.parent().and_then(ElementRef::wrap))
+            .unwrap_or(title);
+
+        // the song text may be in the same table
+        let song_text = Self::find_song_text(document).ok().map(|elem| elem.id());
+
+        let texts: Vec<String> = metadata_element
+            .descendants()
+            .filter(|node| !node.ancestors().any(|a| Some(a.id()) == song_text))
+            .filter_map(|node| node.value().as_text())
+            .map(|t| t.trim().to_string())
+            .filter(|t| t != "")
+            .collect();
+
+        let find_value = |labels: &[&str]| label_values(&texts, labels).next();
+
+        // only a number right after the label, "Capo: 2", "Kapo 3. pražec"
+        let capo = label_values(&texts, &["capo:", "capo", "kapo:", "kapo"]).find_map(|value| {
+            value
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse()
+                .ok()
+        });
+
+        SongMetadata {
+            lyricist: find_value(&["text piesne:", "text písně:", "text:", "slová:", "slova:"]),
+            composer: find_value(&["hudba:"]),
+            album: find_value(&["album:"]),
+            submitter: find_value(&["pridal(a):", "pridal:", "přidal(a):", "přidal:"]),
+            key: find_value(&["tónina:", "tonina:", "key:"]),
+            capo,
+        }
+    }
+
+    pub fn get(
+        document: &scraper::Html,
+        txt_export_document: String,
+        options: Option<Options>,
+    ) -> anyhow::Result<super::core::LyricsWithChords> {
+        let user_options = options.unwrap_or_default();
+
+        let (artist, song_name) = Self::get_title(document)?;
+
         let lf_template = txt_export_document.replace("\r\n", "\n");
         let mut song_template: Vec<&str> = lf_template.split("\n").collect();
 
+        if song_template.len() < 2 || lf_template.trim_start().starts_with("<") {
+            return Err(anyhow::Error::msg(
+                "Unexpected structure of the text export",
+            ));
+        }
+
         // remove whitespace
         song_template.drain(0..2);
 
@@ -52,6 +122,129 @@ impl Supermusic {
             }
         };
 
+        let nodes = Self::correct_chord_positions(nodes);
+
+        let mut lyrics = LyricsWithChords::new(
+            Self::label_sections(nodes, &user_options),
+            artist,
+            song_name,
+        );
+        lyrics.metadata = Self::get_metadata(document);
+
+        Ok(lyrics)
+    }
+
+    // used when the text export is not available,
+    // the chords are read from the markup of the song page itself
+    pub fn get_from_html(
+        document: &Html,
+        options: Option<Options>,
+    ) -> anyhow::Result<LyricsWithChords> {
+        let user_options = options.unwrap_or_default();
+
+        let (artist, song_name) = Self::get_title(document)?;
+
+        let song_text = Self::find_song_text(document)?;
+
+        let mut nodes = Vec::new();
+        let preformatted = song_text.value().name() == "pre";
+        collect_html_nodes(song_text, preformatted, &mut nodes);
+
+        // the page usually has some whitespace around the song
+        while matches!(nodes.first(), Some(TextNode::Newline)) {
+            nodes.remove(0);
+        }
+
+        while matches!(nodes.last(), Some(TextNode::Newline)) {
+            nodes.pop();
+        }
+
+        if !nodes.iter().any(|n| matches!(n, TextNode::Text(_))) {
+            return Err(anyhow::Error::msg("No song text in DOM!"));
+        }
+
+        let nodes = Self::correct_chord_positions(nodes);
+
+        let mut lyrics = LyricsWithChords::new(
+            Self::label_sections(nodes, &user_options),
+            artist,
+            song_name,
+        );
+        lyrics.metadata = Self::get_metadata(document);
+
+        Ok(lyrics)
+    }
+
+    fn find_song_text(document: &Html) -> anyhow::Result<ElementRef<'_>> {
+        for selector in [".piesen", "#piesen", "pre"] {
+            let selector = Selector::parse(selector).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "Failed to create selector!")
+            })?;
+
+            if let Some(elem) = document.select(&selector).nth(0) {
+                return Ok(elem);
+            }
+        }
+
+        // fall back to whatever element holds the chords
+        let chord_selector = Selector::parse("sup").map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "Failed to create selector!")
+        })?;
+
+        document
+            .select(&chord_selector)
+            .nth(0)
+            .and_then(|chord| {
+                chord
+                    .ancestors()
+                    .filter_map(ElementRef::wrap)
+                    .find(|elem| !matches!(elem.value().name(), "a" | "sup" | "b" | "i" | "font"))
+            })
+            .context("No song text in DOM!")
+    }
+
+    // supermusic writes section markers ("R:", "Ref.:", "1.", "2.") as plain text
+    // at the start of the line, we turn them into labels
+    fn label_sections(nodes: Vec<TextNode>, options: &Options) -> Vec<TextNode> {
+        let mut section_counter = SectionCounter::default();
+
+        nodes
+            .split(|n| n == &TextNode::Newline)
+            .map(|line| {
+                let Some(TextNode::Text(first)) = line.first() else {
+                    return line.to_vec();
+                };
+
+                let first = first.trim_start();
+                let Ok((rest, (kind, number))) = section_marker::<(&str, ErrorKind)>(first) else {
+                    return line.to_vec();
+                };
+                let written = &first[..first.len() - rest.len()];
+
+                let mut labeled_line = Vec::new();
+                let rest = match section_counter.relabel(options, kind, number, written) {
+                    Some(label) => {
+                        labeled_line.push(TextNode::Label(label));
+                        rest
+                    }
+                    None => rest.trim_start(),
+                };
+
+                if rest != "" {
+                    labeled_line.push(TextNode::Text(rest.to_string()));
+                } else if line.len() > 1 && labeled_line.len() != 0 {
+                    // keep the label apart from the chord after it
+                    labeled_line.push(TextNode::Text(" ".to_string()));
+                }
+
+                labeled_line.extend(line[1..].iter().cloned());
+                labeled_line
+            })
+            .collect::<Vec<Vec<TextNode>>>()
+            .join(&TextNode::Newline)
+    }
+
+    fn correct_chord_positions(nodes: Vec<TextNode>) -> Vec<TextNode> {
         let mut lines = Vec::new();
         let mut line = Vec::new();
         nodes.iter().for_each(|n| match n {
@@ -62,33 +255,19 @@ impl Supermusic {
             _ => line.push(n.clone()),
         });
 
+        if line.len() != 0 {
+            lines.push(line);
+        }
+
         let mut corrected_lines: Vec<Vec<TextNode>> = Vec::new();
         for line in lines {
-            let get_text = |n| {
-                if let TextNode::Text(t) = n {
-                    t
-                } else {
-                    unreachable!()
-                }
-            };
-
-            let line_text = get_text(
-                line.iter()
-                    .map(|n| n.clone())
-                    .reduce(|acc, node| {
-                        let mut k = TextNode::Text("".to_string());
-                        if let TextNode::Text(_) = acc {
-                            k = acc.clone()
-                        }
-
-                        if let TextNode::Text(t) = node {
-                            TextNode::Text(get_text(k) + &t)
-                        } else {
-                            k
-                        }
-                    })
-                    .unwrap_or(TextNode::Text("".to_string())),
-            );
+            let line_text: String = line
+                .iter()
+                .filter_map(|n| match n {
+                    TextNode::Text(t) => Some(t.as_str()),
+                    _ => None,
+                })
+                .collect();
 
             let mut index = 0;
             let possible_indices: Vec<usize> = line_text
@@ -207,14 +386,14 @@ impl Supermusic {
             );
         }
 
-        Ok(LyricsWithChords::new(
-            corrected_lines.join(&TextNode::Newline),
-            artist.to_string(),
-            song_name.to_string(),
-        ))
+        corrected_lines.join(&TextNode::Newline)
     }
 
-    pub async fn fetch_whole(url: String) -> anyhow::Result<LyricsWithChords> {
+    pub async fn fetch_whole(
+        network: &Network,
+        url: String,
+        options: Option<Options>,
+    ) -> anyhow::Result<LyricsWithChords> {
         let song_id = url
             .split("?")
             .nth(1)
@@ -231,11 +410,88 @@ impl Supermusic {
             song_id
         );
 
-        let client = Client::new();
-        let text_export_response = client.get(text_export_url).send().await?.text().await?;
-        let main_document = client.get(url).send().await?.text().await?;
+        let main_document = network.get_text(&url).await?;
+        let text_export_response = network.get_text(&text_export_url).await;
+
+        let document = Html::parse_document(&main_document);
+
+        // the text export is sometimes disabled or rate limited,
+        // the song page itself has the chords too
+        match text_export_response.and_then(|text| Self::get(&document, text, options.clone())) {
+            Ok(lyrics) if lyrics.text.len() != 0 => Ok(lyrics),
+            _ => Self::get_from_html(&document, options),
+        }
+    }
+}
+
+// the values of the texts starting with one of the labels, the value is either
+// right after the label, or in the next piece of text (when the label is in a <b> tag)
+fn label_values<'a>(texts: &'a [String], labels: &'a [&str]) -> impl Iterator<Item = String> + 'a {
+    texts.iter().enumerate().filter_map(move |(i, t)| {
+        let lowercase = t.to_lowercase();
+        let label = labels.iter().find(|l| lowercase.starts_with(*l))?;
+
+        let value = t.get(label.len()..)?.trim().trim_end_matches(",").trim();
+        let value = if value == "" {
+            texts.get(i + 1)?.trim_end_matches(",").trim()
+        } else {
+            value
+        };
 
-        Self::get(&Html::parse_document(&main_document), text_export_response)
+        if value == "" {
+            None
+        } else {
+            Some(value.to_string())
+        }
+    })
+}
+
+// inside <pre> the newlines are line breaks, everywhere else
+// the line breaks are <br> tags and the newlines are only whitespace
+fn collect_html_nodes(element: ElementRef, preformatted: bool, nodes: &mut Vec<TextNode>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(t) if preformatted => {
+                for (i, line) in t.replace("\r", "").split("\n").enumerate() {
+                    if i != 0 {
+                        nodes.push(TextNode::Newline);
+                    }
+                    if line != "" {
+                        nodes.push(TextNode::Text(line.to_string()));
+                    }
+                }
+            }
+            Node::Text(t) => {
+                let at_line_start = matches!(nodes.last(), None | Some(TextNode::Newline));
+                let text = collapse_newlines(t, at_line_start);
+                if text != "" {
+                    nodes.push(TextNode::Text(text));
+                }
+            }
+            Node::Element(e) => {
+                let child_element = ElementRef::wrap(child).unwrap();
+
+                let is_chord = e.name() == "sup"
+                    || (e.name() == "a"
+                        && (e.classes().any(|c| c == "sup")
+                            || e.attr("href").unwrap_or("").contains("akord")));
+
+                match e.name() {
+                    "br" => nodes.push(TextNode::Newline),
+                    "script" | "style" => {}
+                    _ if is_chord => {
+                        let chord = child_element.text().collect::<String>();
+                        if chord.trim() != "" {
+                            nodes.push(TextNode::Chord(chord.trim().to_string()));
+                        }
+                    }
+                    _ => {
+                        collect_html_nodes(child_element, preformatted || e.name() == "pre", nodes)
+                    }
+                }
+            }
+            _ => {}
+        }
     }
 }
 
@@ -271,6 +527,77 @@ fn newline<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     map(newline_take_while, |_| TextNode::Newline)(i)
 }
 
+fn marker_end<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    peek(alt((multispace1, eof)))(i)
+}
+
+// "R:", "Ref.:", "R2:", "®"
+fn chorus_marker<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (SectionKind, Option<usize>), E> {
+    let chorus_word = alt((
+        tag_no_case("refrén"),
+        tag_no_case("refren"),
+        tag_no_case("ref"),
+        tag_no_case("r"),
+    ));
+    let punctuation = alt((tag(".:"), tag(":"), tag(".")));
+
+    context(
+        "chorus marker",
+        map(
+            terminated(
+                alt((
+                    terminated(pair(chorus_word, digit0), punctuation),
+                    terminated(pair(tag("®"), digit0), opt(alt((tag(":"), tag("."))))),
+                )),
+                marker_end,
+            ),
+            |(_, number): (&str, &str)| (SectionKind::Chorus, number.parse().ok()),
+        ),
+    )(i)
+}
+
+// "1.", "2)", "3:"
+fn verse_marker<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (SectionKind, Option<usize>), E> {
+    context(
+        "verse marker",
+        map(
+            terminated(terminated(digit1, one_of(".):")), marker_end),
+            |number: &str| (SectionKind::Verse, number.parse().ok()),
+        ),
+    )(i)
+}
+
+// "Bridge:", "Mezihra:", "Solo 2:", only the name of the section and a number
+fn named_marker<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (SectionKind, Option<usize>), E> {
+    context(
+        "named marker",
+        map_opt(
+            terminated(
+                terminated(
+                    take_while1(|c: char| c.is_alphanumeric() || c == '-' || c == ' '),
+                    char(':'),
+                ),
+                marker_end,
+            ),
+            SectionKind::from_name,
+        ),
+    )(i)
+}
+
+pub(crate) fn section_marker<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, (SectionKind, Option<usize>), E> {
+    alt((chorus_marker, verse_marker, named_marker))(i)
+}
+
 fn parse_lyrics_with_chords<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     i: &'a str,
 ) -> Result<Vec<TextNode>, E> {
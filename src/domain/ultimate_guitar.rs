@@ -1,7 +1,6 @@
 use std::{collections::HashMap, io};
 
 use anyhow::{Context, Error};
-use itertools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while1, take_while_m_n},
@@ -14,7 +13,12 @@ use nom::{
 use scraper::{Html, Selector};
 use serde_json::Value;
 
-use super::core::{Appendable, LyricsWithChords, Options, TextNode};
+use crate::network::Network;
+
+use super::{
+    alignment::{chord_columns, is_chord_line, merge_chord_line, AlignmentGuess},
+    core::{LyricsWithChords, Options, SectionCounter, TextNode},
+};
 
 pub struct RawParsedData {
     pub artist: String,
@@ -25,8 +29,6 @@ pub struct RawParsedData {
 pub struct UltimateGuitar;
 
 impl UltimateGuitar {
-    const CHORD_CHARACTER_WIDTH: usize = 3;
-
     fn parse_data_from_dom(document: &Html) -> anyhow::Result<RawParsedData> {
         let selector = Selector::parse(".js-store").map_err(|_| {
             io::Error::new(io::ErrorKind::InvalidData, "Failed to create selector!")
@@ -45,7 +47,8 @@ impl UltimateGuitar {
             .to_string()
             .replace("\\\\", "\\");
 
-        let parsed_content: HashMap<String, Value> = serde_json::from_str(&content).unwrap();
+        let parsed_content: HashMap<String, Value> =
+            serde_json::from_str(&content).context("Failed to parse the song data!")?;
 
         //song_name": String("Just"), "artist_id": Number(578), "artist_name": String("Radiohead"),
 
@@ -92,7 +95,36 @@ impl UltimateGuitar {
         });
     }
 
+    pub async fn fetch(
+        network: &Network,
+        url: String,
+        options: Option<Options>,
+    ) -> anyhow::Result<LyricsWithChords> {
+        let text = network.get_text(&url).await?;
+
+        Self::get(&Html::parse_document(&text), options)
+    }
+
+    pub async fn fetch_with_guesses(
+        network: &Network,
+        url: String,
+        options: Option<Options>,
+    ) -> anyhow::Result<(LyricsWithChords, Vec<(usize, AlignmentGuess)>)> {
+        let text = network.get_text(&url).await?;
+
+        Self::get_with_guesses(&Html::parse_document(&text), options)
+    }
+
     pub fn get(document: &Html, options: Option<Options>) -> anyhow::Result<LyricsWithChords> {
+        Self::get_with_guesses(document, options).map(|(lyrics, _)| lyrics)
+    }
+
+    // same as `get`, but also reports the chords whose position had to be guessed,
+    // together with the index of the line they ended up in
+    pub fn get_with_guesses(
+        document: &Html,
+        options: Option<Options>,
+    ) -> anyhow::Result<(LyricsWithChords, Vec<(usize, AlignmentGuess)>)> {
         let user_options = options.unwrap_or_default();
 
         let parsed_data = Self::parse_data_from_dom(document)?;
@@ -108,24 +140,9 @@ impl UltimateGuitar {
             Err((e, kind)) => return Err(Error::msg(format!("{}: {}", kind.description(), e))),
         }
         .iter()
-        .map(|n| {
-            if let TextNode::Chord(ch) = n {
-                if ch.contains("B") {
-                    if ch == "B" {
-                        TextNode::Chord("H".to_string())
-                    } else if ch == "Bb" {
-                        TextNode::Chord("B".to_string())
-                    } else if ch == "B#" {
-                        TextNode::Chord("C".to_string())
-                    } else {
-                        unreachable!()
-                    }
-                } else {
-                    n.clone()
-                }
-            } else {
-                n.clone()
-            }
+        .map(|n| match n {
+            TextNode::Chord(ch) => TextNode::Chord(german_chord(ch)),
+            n => n.clone(),
         })
         .collect();
 
@@ -141,25 +158,29 @@ impl UltimateGuitar {
                 line.push(node);
             }
         }
+        // the text does not have to end with a newline
+        if line.len() != 0 {
+            lines.push(line);
+        }
 
+        let mut section_counter = SectionCounter::default();
+        let mut guesses = Vec::new();
         let mut merged_lines: Vec<Vec<TextNode>> = Vec::new();
         for (i, line) in lines.clone().iter().enumerate() {
             merged_lines.push(line.clone());
 
             if line.iter().any(|n| matches!(n, &TextNode::Label(_))) {
                 if let TextNode::Label(l) = line[0].clone() {
-                    // remove all but chorus labels,
-                    // insert a newline in front of all chorus labels
-                    if !l.to_lowercase().contains("chorus") {
-                        merged_lines.pop();
-                        merged_lines.push(vec![]);
-                        continue;
-                    } else {
-                        merged_lines.pop();
-
-                        merged_lines.push(vec![]);
-                        merged_lines.push(vec![TextNode::Label(user_options.chorus_label.clone())]);
+                    // replace the labels using the user's mapping,
+                    // insert a newline in front of every section
+                    merged_lines.pop();
+                    merged_lines.push(vec![]);
+
+                    if let Some(label) = section_counter.label(&user_options, &l) {
+                        merged_lines.push(vec![TextNode::Label(label)]);
                     }
+
+                    continue;
                 }
             }
 
@@ -175,32 +196,37 @@ impl UltimateGuitar {
             // [Lyrics]
             // ...
             // here we merge the chords with lyrics into one line
-            let previous_line = lines[i - 1].clone();
-            let has_chord = line.iter().any(|n| matches!(n, &TextNode::Chord(_)));
-            let previous_line_has_chord = previous_line
-                .iter()
-                .any(|n| matches!(n, &TextNode::Chord(_)) && !matches!(n, &TextNode::Label(_)));
-
-            if has_chord {
+            if is_chord_line(line) {
                 merged_lines.pop();
 
-                // If there are only chords in this line, we remove the spaces between them
-                // TODO: Detect streaks of chords instead to also delete spaces in lines with both text and chords
+                // chords without lyrics under them (intros, instrumental parts)
+                // stay a streak of chords, we collapse the spaces between them
+                // and keep the bar lines and repeats
                 merged_lines.push(
                     line.iter()
-                        .filter(|n| matches!(*n, TextNode::Chord(_)))
+                        .flat_map(|n| match n {
+                            TextNode::Text(t) => t
+                                .split_whitespace()
+                                .map(|t| TextNode::Text(t.to_string()))
+                                .collect(),
+                            n => vec![n.clone()],
+                        })
                         .enumerate()
                         .flat_map(|(i, n)| {
                             if i != 0 {
-                                vec![TextNode::Text(" ".to_string()), n.clone()]
+                                vec![TextNode::Text(" ".to_string()), n]
                             } else {
-                                vec![n.clone()]
+                                vec![n]
                             }
                         })
                         .collect(),
                 );
                 continue;
-            } else if !previous_line_has_chord {
+            }
+
+            // chords written inline with the text are kept where they are
+            let has_chord = line.iter().any(|n| matches!(n, &TextNode::Chord(_)));
+            if i == 0 || has_chord || !is_chord_line(&lines[i - 1]) {
                 continue;
             }
 
@@ -215,80 +241,26 @@ impl UltimateGuitar {
                 }
             }
 
-            // detect where the borders of words are
-            // we do not want to put chords in the middle of words
-            let mut index = 0;
-            let possible_indices: Vec<usize> = current_line_text_string
-                .split(" ")
-                .enumerate()
-                .flat_map(|(i, t)| {
-                    if i != 0 {
-                        index += 1;
-                    }
-
-                    let result = vec![index, index + t.len()];
-                    index += t.len();
-
-                    result
-                })
-                .dedup()
-                .collect();
+            let alignment =
+                merge_chord_line(&chord_columns(&lines[i - 1]), &current_line_text_string);
 
-            // we start with only text and split it into pieces
-            // putting the chords in between
-            let mut merged_line: Vec<TextNode> =
-                vec![TextNode::Text(current_line_text_string.to_string())];
-
-            let mut target_len = 0;
-            for node in previous_line {
-                match node {
-                    TextNode::Text(ref k) => {
-                        target_len += k.len();
-                    }
-                    TextNode::Chord(ref ch) => {
-                        // we find where this chord should be put
-                        let (_, closest_index) = possible_indices
-                            .iter()
-                            .map(|k| (target_len.abs_diff(*k), *k))
-                            .sorted_by(|(a_diff, _ind1), (b_diff, _ind2)| Ord::cmp(a_diff, b_diff))
-                            .nth(0)
-                            .unwrap();
-
-                        merged_line.push_chord(closest_index, TextNode::Chord(ch.clone()));
-
-                        target_len += Self::CHORD_CHARACTER_WIDTH.max(ch.len());
-                    }
-                    TextNode::Label(_) => unreachable!(),
-                    TextNode::Newline => unreachable!(),
-                }
-            }
+            guesses.extend(
+                alignment
+                    .guesses
+                    .into_iter()
+                    .map(|guess| (merged_lines.len(), guess)),
+            );
 
-            // we delete spaces between streaks of chords
-            merged_line = merged_line
-                .iter()
-                .enumerate()
-                .flat_map(|(i, node)| {
-                    if i == 0 {
-                        return vec![node.clone()];
-                    }
-
-                    if matches!(merged_line[i - 1], TextNode::Chord(_))
-                        && matches!(node, TextNode::Chord(_))
-                    {
-                        return vec![TextNode::Text(" ".to_string()), node.clone()];
-                    }
-
-                    vec![node.clone()]
-                })
-                .collect();
-
-            merged_lines.push(merged_line);
+            merged_lines.push(alignment.line);
         }
 
-        Ok(LyricsWithChords::new(
-            merged_lines.join(&TextNode::Newline),
-            parsed_data.artist,
-            parsed_data.song_name,
+        Ok((
+            LyricsWithChords::new(
+                merged_lines.join(&TextNode::Newline),
+                parsed_data.artist,
+                parsed_data.song_name,
+            ),
+            guesses,
         ))
     }
 }
@@ -337,6 +309,26 @@ fn label<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     )(i)
 }
 
+// ultimate guitar uses the english names, we use the german ones,
+// "B" is "H", "Bb" is "B" and "B#" is "C", also in "Bm7" or "G/B"
+fn german_chord(chord: &str) -> String {
+    chord
+        .split("/")
+        .map(|part| {
+            if let Some(rest) = part.strip_prefix("Bb") {
+                format!("B{}", rest)
+            } else if let Some(rest) = part.strip_prefix("B#") {
+                format!("C{}", rest)
+            } else if let Some(rest) = part.strip_prefix("B") {
+                format!("H{}", rest)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 fn parse_lyrics_with_chords<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     i: &'a str,
 ) -> Result<Vec<TextNode>, E> {
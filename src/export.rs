@@ -1,50 +1,156 @@
+use std::{collections::HashSet, fs, io::Cursor, path::Path};
+
 use docx::{
-    document::{BodyContent, Break, BreakType, Paragraph, Run},
-    Docx,
+    document::{BodyContent, Paragraph},
+    Docx, DocxError, DocxFile,
 };
 use itertools::Itertools;
-use scraper::Html;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::{
-    core::{LyricsWithChords, TextNode},
-    supermusic::Supermusic,
-    ultimate_guitar::UltimateGuitar,
+use crate::{
+    batch::{self, BatchResult, FetchBatches},
+    domain::{
+        alignment::AlignmentGuess,
+        chordpro::ChordPro,
+        core::{ExportOptions, LyricsWithChords, Options, TextNode},
+        docx_layout::{DocxLayout, PageBreak, PageLayout},
+        docx_songbook::DocxSongbook,
+        epub::Epub,
+        html::{Html, HtmlOptions},
+        latex::Latex,
+        odt::Odt,
+        onsong::OnSong,
+        openlyrics::OpenLyrics,
+        pdf::{PdfFonts, PdfOptions, PdfSongbook},
+        plain_text::PlainText,
+        pptx::{Pptx, PptxOptions},
+        supermusic::Supermusic,
+        ultimate_guitar::UltimateGuitar,
+    },
+    network::Network,
 };
 
+// a song together with the chords whose position had to be guessed,
+// so the app can point the user to the lines worth checking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuessedSong {
+    pub song: LyricsWithChords,
+    // the index of the line and the guess made in it
+    pub guesses: Vec<(usize, AlignmentGuess)>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum EditingHint {
     Node(TextNode),
     PossibleChordPlace,
 }
 
+// expects a `Network` to be managed by the tauri app
 #[tauri::command(async)]
-pub async fn fetch(url: String) -> Result<LyricsWithChords, String> {
-    let client = reqwest::Client::new();
+pub async fn fetch(
+    network: tauri::State<'_, Network>,
+    url: String,
+    refresh: Option<bool>,
+    options: Option<Options>,
+) -> Result<LyricsWithChords, String> {
+    let network = if refresh.unwrap_or(false) {
+        network.refreshing()
+    } else {
+        network.inner().clone()
+    };
 
-    if url.contains("ultimate-guitar.com") {
-        let response = match client.get(url).send().await {
-            Ok(r) => r,
-            Err(e) => return Err(e.to_string()),
-        };
+    fetch_song(&network, url, options)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// same as `fetch`, but also returns the guessed chord positions
+#[tauri::command(async)]
+pub async fn fetch_with_guesses(
+    network: tauri::State<'_, Network>,
+    url: String,
+    refresh: Option<bool>,
+    options: Option<Options>,
+) -> Result<GuessedSong, String> {
+    let network = if refresh.unwrap_or(false) {
+        network.refreshing()
+    } else {
+        network.inner().clone()
+    };
+
+    fetch_song_with_guesses(&network, url, options)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-        let text = match response.text().await {
-            Ok(t) => t,
-            Err(e) => return Err(e.to_string()),
-        };
+// emits a "fetch-many" event with a `BatchEvent` for every finished song,
+// expects `FetchBatches` to be managed by the tauri app
+#[tauri::command(async)]
+pub async fn fetch_many(
+    window: tauri::Window,
+    network: tauri::State<'_, Network>,
+    batches: tauri::State<'_, FetchBatches>,
+    batch_id: String,
+    urls: Vec<String>,
+    options: Option<Options>,
+) -> Result<BatchResult, String> {
+    Ok(
+        batch::fetch_many(&network, &batches, &batch_id, urls, options, |event| {
+            // the window may have been closed, there is no one to tell then
+            let _ = window.emit("fetch-many", event);
+        })
+        .await,
+    )
+}
 
-        let document = Html::parse_document(&text);
+#[tauri::command]
+pub fn cancel_fetch_many(batches: tauri::State<'_, FetchBatches>, batch_id: String) -> bool {
+    batches.cancel(&batch_id)
+}
 
-        UltimateGuitar::get(&document, None).map_err(|e| e.to_string())
+#[tauri::command]
+pub fn set_offline_mode(network: tauri::State<'_, Network>, offline: bool) {
+    network.set_offline(offline);
+}
+
+#[tauri::command(async)]
+pub async fn clear_cache(network: tauri::State<'_, Network>) -> Result<(), String> {
+    match network.cache() {
+        Some(cache) => cache.clear().await.map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+pub async fn fetch_song(
+    network: &Network,
+    url: String,
+    options: Option<Options>,
+) -> anyhow::Result<LyricsWithChords> {
+    if url.contains("ultimate-guitar.com") {
+        UltimateGuitar::fetch(network, url, options).await
     } else if url.contains("supermusic.cz") {
-        Supermusic::fetch_whole(url)
-            .await
-            .map_err(|e| e.to_string())
+        Supermusic::fetch_whole(network, url, options).await
     } else {
-        Err("This source is not supported!".to_string())
+        Err(anyhow::Error::msg("This source is not supported!"))
     }
 }
 
+// only the ultimate guitar songs have their chords written above the lyrics,
+// supermusic puts them into the text where they belong
+pub async fn fetch_song_with_guesses(
+    network: &Network,
+    url: String,
+    options: Option<Options>,
+) -> anyhow::Result<GuessedSong> {
+    let (song, guesses) = if url.contains("ultimate-guitar.com") {
+        UltimateGuitar::fetch_with_guesses(network, url, options).await?
+    } else {
+        (fetch_song(network, url, options).await?, Vec::new())
+    };
+
+    Ok(GuessedSong { song, guesses })
+}
+
 #[tauri::command]
 pub fn get_editing_hints(nodes: Vec<TextNode>) -> Vec<EditingHint> {
     nodes
@@ -107,39 +213,157 @@ pub fn get_editing_hints(nodes: Vec<TextNode>) -> Vec<EditingHint> {
         .collect()
 }
 
+// the songs as they should be written by the exporters
+fn export_songs(
+    songs: Vec<LyricsWithChords>,
+    export_options: Option<ExportOptions>,
+) -> Vec<LyricsWithChords> {
+    if export_options.unwrap_or_default().lyrics_only {
+        songs.iter().map(|song| song.without_chords()).collect()
+    } else {
+        songs
+    }
+}
+
+// `page_breaks` sets how every song starts, the songs without it start on a new page,
+// or are kept in one column when the layout has more of them
 #[tauri::command]
-pub fn write_docx(songs: Vec<LyricsWithChords>, path: String) -> Result<(), String> {
+pub fn write_docx(
+    songs: Vec<LyricsWithChords>,
+    path: String,
+    export_options: Option<ExportOptions>,
+    page_breaks: Option<Vec<PageBreak>>,
+    layout: Option<PageLayout>,
+) -> Result<(), String> {
+    let songs = export_songs(songs, export_options);
+    let page_breaks = page_breaks.unwrap_or_default();
+    let layout = layout.unwrap_or_default();
     let mut whole_document = Docx::default();
+    let mut properties = Vec::new();
 
     for (song_i, song) in songs.iter().enumerate() {
-        let song_paragraphs = song.clone().render_docx();
-        for (i, paragraph) in song_paragraphs.iter().enumerate() {
-            if song_i != 0 && i == 0 {
-                let mut p = paragraph.clone();
-
-                // TODO: Make page breaks work
-                p.content.insert(
-                    0,
-                    docx::document::ParagraphContent::Run(
-                        Run::default().push_break(Break::from(BreakType::Page)),
-                    ),
-                );
-
-                whole_document.document.push(p);
-            } else {
-                whole_document.document.push(paragraph.clone());
-            }
+        let page_break = page_breaks
+            .get(song_i)
+            .copied()
+            .unwrap_or(layout.default_page_break());
+
+        for (paragraph, paragraph_properties) in
+            DocxLayout::song(song.clone().render_docx(), page_break, song_i == 0, &layout)
+        {
+            whole_document.document.push(paragraph);
+            properties.push(paragraph_properties);
         }
     }
 
-    whole_document
-        .write_file(path)
-        .map_err(|e| match e {
-            docx::DocxError::IO(e) => e.to_string(),
-            docx::DocxError::Xml(_e) => "Xml Error!".to_string(),
-            docx::DocxError::Zip(e) => e.to_string(),
-        })
-        .map(|_| ())
+    let mut buffer = Cursor::new(Vec::new());
+    whole_document.write(&mut buffer).map_err(docx_error)?;
+    let docx =
+        DocxLayout::apply(buffer.into_inner(), &properties, &layout).map_err(|e| e.to_string())?;
+
+    fs::write(path, docx).map_err(|e| e.to_string())
+}
+
+// the fonts are truetype files, they are embedded into the pdf
+#[tauri::command]
+pub fn write_pdf(
+    songs: Vec<LyricsWithChords>,
+    path: String,
+    font_path: String,
+    bold_font_path: Option<String>,
+    options: Option<PdfOptions>,
+) -> Result<(), String> {
+    let fonts = PdfFonts {
+        regular: fs::read(font_path).map_err(|e| e.to_string())?,
+        bold: match bold_font_path {
+            Some(bold_font_path) => Some(fs::read(bold_font_path).map_err(|e| e.to_string())?),
+            None => None,
+        },
+    };
+
+    let pdf = PdfSongbook::render(&songs, &fonts, &options.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+
+    fs::write(path, pdf).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn write_html(
+    songs: Vec<LyricsWithChords>,
+    path: String,
+    options: Option<HtmlOptions>,
+    export_options: Option<ExportOptions>,
+) -> Result<(), String> {
+    let songs = export_songs(songs, export_options);
+    let html = Html::render_songbook(&songs, None, &options.unwrap_or_default());
+
+    fs::write(path, html).map_err(|e| e.to_string())
+}
+
+// a document for the `songs` package, typeset with pdflatex
+#[tauri::command]
+pub fn write_latex(songs: Vec<LyricsWithChords>, path: String) -> Result<(), String> {
+    fs::write(path, Latex::render_songbook(&songs, None)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn write_epub(
+    songs: Vec<LyricsWithChords>,
+    path: String,
+    title: Option<String>,
+) -> Result<(), String> {
+    let epub = Epub::render(&songs, None, &title.unwrap_or("Songbook".to_string()))
+        .map_err(|e| e.to_string())?;
+
+    fs::write(path, epub).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn write_odt(songs: Vec<LyricsWithChords>, path: String) -> Result<(), String> {
+    let odt = Odt::render(&songs).map_err(|e| e.to_string())?;
+
+    fs::write(path, odt).map_err(|e| e.to_string())
+}
+
+// slides for projecting the lyrics at singalongs
+#[tauri::command]
+pub fn write_pptx(
+    songs: Vec<LyricsWithChords>,
+    path: String,
+    options: Option<PptxOptions>,
+) -> Result<(), String> {
+    let pptx =
+        Pptx::render(&songs, None, &options.unwrap_or_default()).map_err(|e| e.to_string())?;
+
+    fs::write(path, pptx).map_err(|e| e.to_string())
+}
+
+// the same markup as in `write_html`, for the preview in the app
+#[tauri::command]
+pub fn render_html(song: LyricsWithChords, export_options: Option<ExportOptions>) -> String {
+    let song = export_songs(vec![song], export_options).remove(0);
+
+    format!(
+        "<style>{}</style>\n{}",
+        Html::stylesheet(),
+        Html::render_song(&song, None)
+    )
+}
+
+// songbooks written by `write_docx`, so they can be edited again
+#[tauri::command]
+pub fn import_docx(path: String) -> Result<Vec<LyricsWithChords>, String> {
+    let file = DocxFile::from_file(path).map_err(docx_error)?;
+    let document = file.parse().map_err(docx_error)?;
+
+    DocxSongbook::parse(&document).map_err(|e| e.to_string())
+}
+
+fn docx_error(e: DocxError) -> String {
+    match e {
+        DocxError::IO(e) => e.to_string(),
+        DocxError::Xml(_e) => "Xml Error!".to_string(),
+        DocxError::Zip(e) => e.to_string(),
+    }
 }
 
 #[tauri::command]
@@ -150,3 +374,147 @@ pub fn transpose(nodes: Vec<TextNode>, modifier: i32) -> Vec<TextNode> {
 
     return dummy_lyrics.text;
 }
+
+#[tauri::command]
+pub fn import_chordpro(
+    path: String,
+    options: Option<Options>,
+) -> Result<Vec<LyricsWithChords>, String> {
+    let extension = Path::new(&path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    if !ChordPro::EXTENSIONS.contains(&extension.as_str()) {
+        return Err("This is not a ChordPro file!".to_string());
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    ChordPro::parse(&content, options).map_err(|e| e.to_string())
+}
+
+// writes the whole songbook into one file,
+// or into a directory with a file for every song
+#[tauri::command]
+pub fn write_chordpro(
+    songs: Vec<LyricsWithChords>,
+    path: String,
+    single_file: bool,
+) -> Result<(), String> {
+    if single_file {
+        return fs::write(path, ChordPro::render_songbook(&songs, None)).map_err(|e| e.to_string());
+    }
+
+    write_song_files(&songs, &path, "cho", |song| {
+        Ok(ChordPro::render(song, None))
+    })
+}
+
+#[tauri::command]
+pub fn import_openlyrics(
+    path: String,
+    options: Option<Options>,
+) -> Result<LyricsWithChords, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    OpenLyrics::parse(&content, options).map_err(|e| e.to_string())
+}
+
+// openlyrics has one song per file
+#[tauri::command]
+pub fn write_openlyrics(songs: Vec<LyricsWithChords>, directory: String) -> Result<(), String> {
+    write_song_files(&songs, &directory, "xml", |song| {
+        OpenLyrics::render(song, None)
+    })
+}
+
+#[tauri::command]
+pub fn import_onsong(path: String, options: Option<Options>) -> Result<LyricsWithChords, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    OnSong::parse(&content, options).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn write_onsong(songs: Vec<LyricsWithChords>, directory: String) -> Result<(), String> {
+    write_song_files(&songs, &directory, OnSong::EXTENSION, |song| {
+        Ok(OnSong::render(song, None))
+    })
+}
+
+// a chord sheet pasted by the user, with the chords above the lyrics
+#[tauri::command]
+pub fn import_text(text: String, options: Option<Options>) -> Result<LyricsWithChords, String> {
+    PlainText::parse(&text, options).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_text_with_guesses(
+    text: String,
+    options: Option<Options>,
+) -> Result<GuessedSong, String> {
+    PlainText::parse_with_guesses(&text, options)
+        .map(|(song, guesses)| GuessedSong { song, guesses })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn render_text(song: LyricsWithChords, export_options: Option<ExportOptions>) -> String {
+    PlainText::render(&export_songs(vec![song], export_options)[0])
+}
+
+#[tauri::command]
+pub fn write_text(
+    songs: Vec<LyricsWithChords>,
+    directory: String,
+    export_options: Option<ExportOptions>,
+) -> Result<(), String> {
+    let songs = export_songs(songs, export_options);
+
+    write_song_files(&songs, &directory, "txt", |song| {
+        Ok(PlainText::render(song))
+    })
+}
+
+fn write_song_files(
+    songs: &[LyricsWithChords],
+    directory: &str,
+    extension: &str,
+    render: impl Fn(&LyricsWithChords) -> anyhow::Result<String>,
+) -> Result<(), String> {
+    fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+
+    let mut used_names = HashSet::new();
+    for song in songs {
+        let name = song_file_name(song);
+
+        // two songs with the same name must not overwrite each other
+        let mut unique_name = name.clone();
+        let mut i = 2;
+        while !used_names.insert(unique_name.to_lowercase()) {
+            unique_name = format!("{} ({})", name, i);
+            i += 1;
+        }
+
+        let file_path = Path::new(directory).join(format!("{}.{}", unique_name, extension));
+        let content = render(song).map_err(|e| e.to_string())?;
+        fs::write(file_path, content).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn song_file_name(song: &LyricsWithChords) -> String {
+    format!("{} - {}", song.artist, song.song_name)
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
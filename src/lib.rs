@@ -1,2 +1,5 @@
+pub mod batch;
+pub mod cache;
 pub mod domain;
 pub mod export;
+pub mod network;
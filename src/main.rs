@@ -1,9 +1,13 @@
 use domain::{core::LyricsWithChords, supermusic::Supermusic, ultimate_guitar::UltimateGuitar};
 use export::{get_editing_hints, write_docx};
+use network::Network;
 use scraper::Html;
 
+pub mod batch;
+pub mod cache;
 pub mod domain;
 pub mod export;
+pub mod network;
 
 #[tokio::main]
 async fn main() {
@@ -25,11 +29,13 @@ async fn main() {
 
     // let lyrics = Supermusic::get(&document, &Html::parse_document(&text_1)).unwrap();
 
-    let lyrics = Supermusic::fetch_whole(url.to_string()).await.unwrap();
+    let lyrics = Supermusic::fetch_whole(&Network::default(), url.to_string(), None)
+        .await
+        .unwrap();
 
     // let mut doc = lyrics.render_docx();
 
     // println!("{:?}", get_editing_hints(lyrics.text.clone()));
 
-    write_docx(vec![lyrics], "songs.docx".to_string()).unwrap();
+    write_docx(vec![lyrics], "songs.docx".to_string(), None, None, None).unwrap();
 }
@@ -0,0 +1,280 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Context;
+use reqwest::{header::RETRY_AFTER, Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::{sleep, sleep_until, Instant},
+};
+
+use crate::cache::{CachedPage, PageCache};
+
+// all requests of the scrapers go through here, so they share one client
+// and the sites can be swapped for a local server
+#[derive(Clone)]
+pub struct Network {
+    client: Client,
+    policy: NetworkPolicy,
+    hosts: Arc<Mutex<HashMap<String, Arc<HostLimiter>>>>,
+    base_urls: HashMap<String, String>,
+    cache: Option<PageCache>,
+    offline: Arc<AtomicBool>,
+    refresh: bool,
+}
+
+// how politely we talk to the sites, so bulk imports do not get us blocked
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkPolicy {
+    // how many times a request failing with 429, 5xx or a timeout is repeated
+    pub retries: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub max_concurrent_requests_per_host: usize,
+    // the minimal time between the starts of two requests to the same host
+    pub min_request_interval_ms: u64,
+    pub timeout_ms: u64,
+    pub user_agent: String,
+}
+
+impl Default for NetworkPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+            max_concurrent_requests_per_host: 2,
+            min_request_interval_ms: 500,
+            timeout_ms: 30_000,
+            user_agent: format!("spevnikovac-3000/{}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+struct HostLimiter {
+    permits: Semaphore,
+    next_request: tokio::sync::Mutex<Instant>,
+}
+
+impl HostLimiter {
+    fn new(policy: &NetworkPolicy) -> Self {
+        Self {
+            permits: Semaphore::new(policy.max_concurrent_requests_per_host.max(1)),
+            next_request: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn acquire(&self, min_interval: Duration) -> SemaphorePermit<'_> {
+        // the semaphore is never closed
+        let permit = self.permits.acquire().await.unwrap();
+
+        let mut next_request = self.next_request.lock().await;
+        sleep_until(*next_request).await;
+        *next_request = Instant::now() + min_interval;
+
+        permit
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self::from_policy(NetworkPolicy::default()).expect("Failed to create the http client!")
+    }
+}
+
+impl Network {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            policy: NetworkPolicy::default(),
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+            base_urls: HashMap::new(),
+            cache: None,
+            offline: Arc::new(AtomicBool::new(false)),
+            refresh: false,
+        }
+    }
+
+    pub fn from_policy(policy: NetworkPolicy) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .user_agent(policy.user_agent.clone())
+            .timeout(Duration::from_millis(policy.timeout_ms))
+            .build()?;
+
+        Ok(Self {
+            policy,
+            ..Self::new(client)
+        })
+    }
+
+    pub fn policy(&self) -> &NetworkPolicy {
+        &self.policy
+    }
+
+    pub fn with_cache(mut self, cache: PageCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn cache(&self) -> Option<&PageCache> {
+        self.cache.as_ref()
+    }
+
+    // in offline mode, pages are only served from the cache
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    // a copy of this network which ignores the cached pages
+    // and replaces them with freshly downloaded ones
+    pub fn refreshing(&self) -> Self {
+        Self {
+            refresh: true,
+            ..self.clone()
+        }
+    }
+
+    // requests to `host` are sent to `base_url` instead,
+    // e.g. ("supermusic.cz", "http://127.0.0.1:8080")
+    pub fn with_base_url(mut self, host: &str, base_url: &str) -> Self {
+        self.base_urls
+            .insert(host.to_string(), base_url.trim_end_matches("/").to_string());
+
+        self
+    }
+
+    pub fn resolve(&self, url: &str) -> anyhow::Result<Url> {
+        let parsed_url = Url::parse(url)?;
+
+        let host = parsed_url.host_str().context("Url without a host!")?;
+        let Some(base_url) = self
+            .base_urls
+            .get(host)
+            .or(self.base_urls.get(host.trim_start_matches("www.")))
+        else {
+            return Ok(parsed_url);
+        };
+
+        let mut resolved_url = base_url.clone() + parsed_url.path();
+        if let Some(query) = parsed_url.query() {
+            resolved_url += &format!("?{}", query);
+        }
+
+        Ok(Url::parse(&resolved_url)?)
+    }
+
+    pub async fn get_text(&self, url: &str) -> anyhow::Result<String> {
+        let Some(cache) = &self.cache else {
+            if self.is_offline() {
+                return Err(anyhow::Error::msg(
+                    "Offline mode is enabled, but there is no cache!",
+                ));
+            }
+
+            return self.download(url).await;
+        };
+
+        let cached_page = if self.refresh && !self.is_offline() {
+            CachedPage::Missing
+        } else {
+            cache.get(url).await
+        };
+
+        match cached_page {
+            CachedPage::Fresh(content) => Ok(content),
+            CachedPage::Stale(content) if self.is_offline() => Ok(content),
+            CachedPage::Missing if self.is_offline() => Err(anyhow::Error::msg(format!(
+                "{} is not cached and offline mode is enabled!",
+                url
+            ))),
+            cached_page => match self.download(url).await {
+                Ok(content) => {
+                    // a failed write only means we download the page again next time
+                    let _ = cache.put(url, &content).await;
+
+                    Ok(content)
+                }
+                // an old page is better than none
+                Err(e) => match cached_page {
+                    CachedPage::Stale(content) => Ok(content),
+                    _ => Err(e),
+                },
+            },
+        }
+    }
+
+    fn host_limiter(&self, host: &str) -> Arc<HostLimiter> {
+        self.hosts
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(HostLimiter::new(&self.policy)))
+            .clone()
+    }
+
+    async fn download(&self, url: &str) -> anyhow::Result<String> {
+        let url = self.resolve(url)?;
+        let limiter = self.host_limiter(url.host_str().unwrap_or(""));
+
+        let max_backoff = Duration::from_millis(self.policy.max_backoff_ms);
+        let mut backoff = Duration::from_millis(self.policy.initial_backoff_ms);
+        let mut attempt = 0;
+        loop {
+            let response = {
+                let _permit = limiter
+                    .acquire(Duration::from_millis(self.policy.min_request_interval_ms))
+                    .await;
+
+                match self.client.get(url.clone()).send().await {
+                    Ok(r) if r.status().is_success() => return Ok(r.text().await?),
+                    response => response,
+                }
+            };
+
+            let can_retry = attempt < self.policy.retries;
+            let wait = match response {
+                Ok(r)
+                    if can_retry
+                        && (r.status() == StatusCode::TOO_MANY_REQUESTS
+                            || r.status().is_server_error()) =>
+                {
+                    // the server may tell us how long to wait (in seconds)
+                    r.headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.trim().parse().ok())
+                        .map(Duration::from_secs)
+                        .unwrap_or(backoff)
+                }
+                // the redirects which are not followed end here too
+                Ok(r) => {
+                    return Err(anyhow::Error::msg(format!(
+                        "{} returned {}",
+                        url,
+                        r.status()
+                    )))
+                }
+                Err(e) if can_retry && (e.is_timeout() || e.is_connect()) => backoff,
+                Err(e) => return Err(e.into()),
+            };
+
+            // the wait the server asks for is capped by the policy too
+            sleep(wait.min(max_backoff).max(backoff)).await;
+
+            backoff = (backoff * 2).min(max_backoff);
+            attempt += 1;
+        }
+    }
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use scraper::Html;
+use spevnik::domain::{
+    alignment::{is_chord_line, is_filler, AlignmentGuess},
+    core::TextNode,
+    plain_text::PlainText,
+    ultimate_guitar::UltimateGuitar,
+};
+
+use common::{fixture, inline, ultimate_guitar_page};
+
+fn chord(ch: &str) -> TextNode {
+    TextNode::Chord(ch.to_string())
+}
+
+fn text(t: &str) -> TextNode {
+    TextNode::Text(t.to_string())
+}
+
+fn ultimate_guitar(content: &str) -> String {
+    let page = ultimate_guitar_page("Artist", "Song", content);
+
+    inline(&UltimateGuitar::get(&Html::parse_document(&page), None).unwrap())
+}
+
+#[test]
+fn fillers() {
+    for token in [
+        "|", "||", "|:", ":|", "/", "%", "-", "x2", "2x", "(2x)", "(x4)", "N.C.", "nc",
+    ] {
+        assert!(is_filler(token), "{}", token);
+    }
+
+    for token in ["to", "x", "()", "Am", "(Am)", "N.C.C."] {
+        assert!(!is_filler(token), "{}", token);
+    }
+}
+
+#[test]
+fn chord_lines() {
+    assert!(is_chord_line(&[chord("Am"), text("   "), chord("F")]));
+    assert!(is_chord_line(&[
+        text("| "),
+        chord("Am"),
+        text(" | "),
+        chord("F"),
+        text(" |")
+    ]));
+    assert!(is_chord_line(&[
+        chord("Am"),
+        text("  "),
+        chord("F"),
+        text("  (2x)")
+    ]));
+    assert!(is_chord_line(&[text("N.C.    "), chord("G")]));
+
+    assert!(!is_chord_line(&[text("|  x2")]));
+    assert!(!is_chord_line(&[chord("Am"), text(" to "), chord("F")]));
+    assert!(!is_chord_line(&[
+        chord("Am"),
+        TextNode::Label("Chorus".to_string())
+    ]));
+}
+
+// lines copied from real chord sheets, the chords are written above the lyrics
+const CORPUS: &[(&str, &str, &str)] = &[
+    (
+        "Am   F",
+        "Hello darkness my old friend",
+        "[Am]Hello[F] darkness my old friend",
+    ),
+    (
+        "| Am | F |",
+        "Hello darkness my old friend",
+        "He[Am]llo d[F]arkness my old friend",
+    ),
+    (
+        "Am   F  (2x)",
+        "Hello darkness my old friend",
+        "[Am]Hello[F] darkness my old friend",
+    ),
+    (
+        "Am   F  x2",
+        "Hello darkness my old friend",
+        "[Am]Hello[F] darkness my old friend",
+    ),
+    (
+        "N.C.    G",
+        "Hello darkness my old friend",
+        "Hello da[G]rkness my old friend",
+    ),
+    (
+        "C    G/E   Am   % |",
+        "Let it be, let it be",
+        "[C]Let i[G/E]t be, [Am]let it be",
+    ),
+];
+
+#[test]
+fn ultimate_guitar_corpus() {
+    for (chords, lyrics, expected) in CORPUS {
+        let content = format!(
+            "{}\r\n{}",
+            chords
+                .split(' ')
+                .map(|t| match t {
+                    "" => String::new(),
+                    t if is_filler(t) => t.to_string(),
+                    t => format!("[ch]{}[/ch]", t),
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            lyrics
+        );
+
+        assert_eq!(ultimate_guitar(&content), *expected, "{}", chords);
+    }
+}
+
+#[test]
+fn plain_text_corpus() {
+    for (chords, lyrics, expected) in CORPUS {
+        let song = PlainText::parse(&format!("{}\n{}", chords, lyrics), None).unwrap();
+
+        assert_eq!(inline(&song), *expected, "{}", chords);
+    }
+}
+
+#[test]
+fn chords_without_lyrics() {
+    assert_eq!(
+        ultimate_guitar("[Chorus]\r\n| [ch]Am[/ch] | [ch]F[/ch] |   x2\r\n\r\n[ch]C[/ch]\r\nHello"),
+        "\n{®:}\n| [Am] | [F] | x2\n[C]Hello"
+    );
+}
+
+#[test]
+fn a_word_is_not_a_filler() {
+    assert_eq!(
+        ultimate_guitar("[ch]Am[/ch]   [ch]F[/ch] to\r\nHello darkness"),
+        "[Am]   [F] to\nHello darkness"
+    );
+}
+
+#[test]
+fn saved_page() {
+    let page = fixture("ultimate_guitar.html");
+    let (song, guesses) =
+        UltimateGuitar::get_with_guesses(&Html::parse_document(&page), None).unwrap();
+
+    assert_eq!(song.artist, "Simon & Garfunkel");
+    assert_eq!(song.song_name, "The Sound of Silence");
+
+    let text = inline(&song);
+    for line in [
+        "{Intro}",
+        "| [Am] | [F] | [C] | [G] | (2x)",
+        "{Verse 1}",
+        "[Am]Hello darkness, my old [G]friend",
+        "{®:}",
+        "And the [F]vision that was [C]planted",
+    ] {
+        assert!(text.lines().any(|l| l == line), "{}", text);
+    }
+
+    // the last chords are further right than the lyrics
+    assert_eq!(
+        guesses
+            .iter()
+            .map(|(_, guess)| guess.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            AlignmentGuess::Overflow {
+                chord: "C".to_string(),
+                column: 16,
+            },
+            AlignmentGuess::Overflow {
+                chord: "G".to_string(),
+                column: 30,
+            },
+        ]
+    );
+}
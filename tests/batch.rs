@@ -0,0 +1,64 @@
+mod common;
+
+use std::sync::Mutex;
+
+use spevnik::{
+    batch::{fetch_many, BatchEvent, FetchBatches},
+    network::{Network, NetworkPolicy},
+};
+
+use common::{
+    server::{Response, TestServer},
+    ultimate_guitar_page,
+};
+
+#[tokio::test]
+async fn failed_songs_are_reported() {
+    let page = ultimate_guitar_page("Artist", "Song", "[ch]Am[/ch]\r\nHello");
+    let server = TestServer::start(vec![("/tab/1", vec![Response::ok(&page)])]).await;
+
+    let network = Network::from_policy(NetworkPolicy {
+        retries: 0,
+        min_request_interval_ms: 0,
+        ..NetworkPolicy::default()
+    })
+    .unwrap()
+    .with_base_url("tabs.ultimate-guitar.com", &server.base_url);
+
+    let events = Mutex::new(Vec::new());
+    let result = fetch_many(
+        &network,
+        &FetchBatches::default(),
+        "batch",
+        vec![
+            "https://example.com/song".to_string(),
+            "https://tabs.ultimate-guitar.com/tab/1".to_string(),
+            "https://tabs.ultimate-guitar.com/tab/2".to_string(),
+        ],
+        None,
+        |event| events.lock().unwrap().push(event),
+    )
+    .await;
+
+    assert!(!result.cancelled);
+    assert_eq!(result.songs.len(), 1);
+    assert_eq!(result.songs[0].song_name, "Song");
+    assert_eq!(
+        result.errors.iter().map(|e| e.index).collect::<Vec<_>>(),
+        vec![0, 2]
+    );
+
+    let events = events.into_inner().unwrap();
+    assert_eq!(events.len(), 3);
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, BatchEvent::Fetched { index: 1, .. })));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        BatchEvent::Failed {
+            index: 2,
+            total: 3,
+            ..
+        }
+    )));
+}
@@ -0,0 +1,285 @@
+mod common;
+
+use spevnik::domain::{
+    chordpro::ChordPro,
+    core::{LyricsWithChords, TextNode},
+};
+
+use common::inline;
+
+fn parse_one(input: &str) -> String {
+    let songs = ChordPro::parse(input, None).unwrap();
+    assert_eq!(songs.len(), 1);
+
+    inline(&songs[0])
+}
+
+#[test]
+fn directives() {
+    let songs = ChordPro::parse(
+        "{title: Song}\n{artist: Band}\n{composer: Composer}\n{lyricist: Lyricist}\n\
+         {album: Album}\n{key: Am}\n{capo: 2}\n{unknown: value}\n[Am]text",
+        None,
+    )
+    .unwrap();
+    let song = &songs[0];
+
+    assert_eq!(song.song_name, "Song");
+    assert_eq!(song.artist, "Band");
+    assert_eq!(song.metadata.composer.as_deref(), Some("Composer"));
+    assert_eq!(song.metadata.lyricist.as_deref(), Some("Lyricist"));
+    assert_eq!(song.metadata.album.as_deref(), Some("Album"));
+    assert_eq!(song.metadata.key.as_deref(), Some("Am"));
+    assert_eq!(song.metadata.capo, Some(2));
+    assert_eq!(inline(song), "[Am]text");
+}
+
+#[test]
+fn short_directives() {
+    let songs = ChordPro::parse("{t:Song}\n{st: Band}\n{c: Intro}\n[G]", None).unwrap();
+
+    assert_eq!(songs[0].song_name, "Song");
+    assert_eq!(songs[0].artist, "Band");
+    assert_eq!(inline(&songs[0]), "{Intro}\n[G]");
+}
+
+#[test]
+fn spaced_directives() {
+    let songs = ChordPro::parse(
+        "{title\u{a0}Song}\n{artist\u{3000}Band}\n{key : Am}\n[Am]text",
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(songs[0].song_name, "Song");
+    assert_eq!(songs[0].artist, "Band");
+    assert_eq!(songs[0].metadata.key.as_deref(), Some("Am"));
+}
+
+#[test]
+fn chorus() {
+    assert_eq!(
+        parse_one("[Am]Verse [C]line\n{soc}\n[F]Chorus [G]line\n{eoc}\nAfter"),
+        "[Am]Verse [C]line\n\n{®:}\n[F]Chorus [G]line\n\nAfter"
+    );
+    assert_eq!(
+        parse_one("{start_of_chorus}\nChorus\n{end_of_chorus}"),
+        "{®:}\nChorus"
+    );
+}
+
+#[test]
+fn named_chorus() {
+    assert_eq!(parse_one("{soc: Refrain}\nx\n{eoc}"), "{Refrain}\nx");
+}
+
+#[test]
+fn chorus_reference() {
+    assert_eq!(
+        parse_one("{soc}\nChorus\n{eoc}\n\nVerse\n{chorus}\nVerse"),
+        "{®:}\nChorus\n\nVerse\n\n{®:}\n\nVerse"
+    );
+}
+
+#[test]
+fn verses_and_bridges() {
+    assert_eq!(
+        parse_one("{sov}\na\n{eov}\n{start_of_bridge}\nb\n{end_of_bridge}"),
+        "a\n\nb"
+    );
+}
+
+#[test]
+fn tab_blocks() {
+    // the tab is kept as it is, with its brackets, comments and blank lines
+    assert_eq!(
+        parse_one("text\n{sot}\ne|--[0]--|\n# not a comment\n\nB|--1--|\n{eot}\n[C]text"),
+        "text\n\ne|--[0]--|\n# not a comment\n\nB|--1--|\n\n[C]text"
+    );
+}
+
+#[test]
+fn comments() {
+    assert_eq!(parse_one("# a comment\n[G]text\n  # indented"), "[G]text");
+}
+
+#[test]
+fn new_song() {
+    let songs = ChordPro::parse(
+        "{title: One}\nfirst\n{new_song}\n{title: Two}\nsecond\n{ns}\n{title: Three}\nthird\n",
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(
+        songs
+            .iter()
+            .map(|s| (s.song_name.as_str(), inline(s)))
+            .collect::<Vec<_>>(),
+        vec![
+            ("One", "first".to_string()),
+            ("Two", "second".to_string()),
+            ("Three", "third".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn new_song_without_songs() {
+    // a separator at the start or a doubled one is not an empty song
+    let songs =
+        ChordPro::parse("{new_song}\n{title: One}\n{new_song}\n{new_song}\n", None).unwrap();
+
+    assert_eq!(songs.len(), 1);
+    assert_eq!(songs[0].song_name, "One");
+}
+
+#[test]
+fn stray_brackets() {
+    for line in ["[Am", "text]", "a ] b [ c", "[]"] {
+        let songs = ChordPro::parse(line, None).unwrap();
+
+        assert!(
+            songs[0].text.iter().all(|n| matches!(n, TextNode::Text(_))),
+            "{}",
+            line
+        );
+        assert_eq!(inline(&songs[0]), line);
+    }
+
+    assert_eq!(parse_one("a [Am] b ]"), "a [Am] b ]");
+}
+
+#[test]
+fn windows_newlines() {
+    assert_eq!(
+        parse_one("{title: Song}\r\n[Am]a\r\n\r\n[C]b\r\n"),
+        "[Am]a\n\n[C]b"
+    );
+}
+
+fn song(text: &str) -> LyricsWithChords {
+    let mut songs = ChordPro::parse(
+        &format!("{{title: Song}}\n{{artist: Band}}\n{}", text),
+        None,
+    )
+    .unwrap();
+
+    songs.remove(0)
+}
+
+fn round_trip(song: &LyricsWithChords) -> LyricsWithChords {
+    let mut songs = ChordPro::parse(&ChordPro::render(song, None), None).unwrap();
+    assert_eq!(songs.len(), 1);
+
+    songs.remove(0)
+}
+
+#[test]
+fn render() {
+    let mut song = song("[Am]Verse [C]line\n\n{soc}\n[F]Chorus\n{eoc}\n{c: Bridge}\n[G]last");
+    song.metadata.key = Some("Am".to_string());
+    song.metadata.capo = Some(3);
+
+    assert_eq!(
+        ChordPro::render(&song, None),
+        "{title: Song}\n{artist: Band}\n{key: Am}\n{capo: 3}\n\n\
+         [Am]Verse [C]line\n\n{soc}\n[F]Chorus\n{eoc}\n\n{c: Bridge}\n[G]last\n"
+    );
+}
+
+#[test]
+fn render_chorus_reference() {
+    let song = song("{soc}\nChorus\n{eoc}\nVerse\n{chorus}\nEnd");
+
+    assert_eq!(
+        ChordPro::render(&song, None),
+        "{title: Song}\n{artist: Band}\n\n{soc}\nChorus\n{eoc}\n\nVerse\n\n{chorus}\n\nEnd\n"
+    );
+}
+
+#[test]
+fn render_named_chorus() {
+    let song = song("{soc: Refrain}\nChorus\n{eoc}\n{chorus: Refrain}");
+    let rendered = ChordPro::render(&song, None);
+
+    assert!(
+        rendered.contains("{soc: Refrain}\nChorus\n{eoc}"),
+        "{}",
+        rendered
+    );
+    assert!(rendered.contains("{chorus: Refrain}"), "{}", rendered);
+}
+
+#[test]
+fn render_songbook() {
+    let songs = ChordPro::parse(
+        "{title: One}\nfirst\n{new_song}\n{title: Two}\nsecond",
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(
+        ChordPro::render_songbook(&songs, None),
+        "{title: One}\n\nfirst\n\n{new_song}\n{title: Two}\n\nsecond\n"
+    );
+    assert_eq!(
+        ChordPro::parse(&ChordPro::render_songbook(&songs, None), None)
+            .unwrap()
+            .iter()
+            .map(inline)
+            .collect::<Vec<_>>(),
+        vec!["first", "second"]
+    );
+}
+
+#[test]
+fn render_round_trip() {
+    let mut song = song(
+        "{c: Intro}\n[Am] [C] [G]\n\n[Am]Verse [C]line\n[G]second line\n\n\
+         {soc}\n[F]Chorus [G]line\n{eoc}\n\n{soc: Refrain}\nx\n{eoc}\n\nEnd",
+    );
+    song.metadata.composer = Some("Composer".to_string());
+    song.metadata.capo = Some(2);
+
+    let read_back = round_trip(&song);
+
+    assert_eq!(inline(&read_back), inline(&song));
+    assert_eq!(read_back.song_name, song.song_name);
+    assert_eq!(read_back.artist, song.artist);
+    assert_eq!(read_back.metadata, song.metadata);
+}
+
+#[test]
+fn render_literal_lines() {
+    // the tab and the brackets which are not chords must not turn into chords or comments
+    let mut song = song("[Am]text\n\n{sot}\ne|--[0]--|\n# not a comment\n{eot}");
+    song.text.extend([
+        TextNode::Newline,
+        TextNode::Newline,
+        TextNode::Text("a ] b [ c".to_string()),
+        TextNode::Newline,
+        TextNode::Text("{not a directive}".to_string()),
+    ]);
+
+    assert_eq!(
+        ChordPro::render(&song, None),
+        "{title: Song}\n{artist: Band}\n\n[Am]text\n\n{sot}\ne|--[0]--|\n# not a comment\n{eot}\n\n\
+         {sot}\na ] b [ c\n{not a directive}\n{eot}\n"
+    );
+    assert_eq!(inline(&round_trip(&song)), inline(&song));
+}
+
+#[test]
+fn render_spaced_directive_lines() {
+    let mut song = song("[Am]text");
+    song.text.extend([
+        TextNode::Newline,
+        TextNode::Newline,
+        TextNode::Text("{title\u{a0}Song}".to_string()),
+        TextNode::Newline,
+        TextNode::Text("{key\u{3000}: Am}".to_string()),
+    ]);
+
+    assert_eq!(inline(&round_trip(&song)), inline(&song));
+}
@@ -0,0 +1,105 @@
+#![allow(dead_code)]
+
+pub mod server;
+
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::PathBuf,
+};
+
+use spevnik::domain::core::{LyricsWithChords, TextNode};
+use zip::ZipArchive;
+
+pub fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+
+    fs::read_to_string(path).unwrap()
+}
+
+// the song text with the chords in brackets and the labels in braces,
+// "{Verse 1}\n[Am]Hello [F]darkness"
+pub fn inline(song: &LyricsWithChords) -> String {
+    inline_nodes(&song.text)
+}
+
+pub fn inline_nodes(nodes: &[TextNode]) -> String {
+    nodes
+        .iter()
+        .map(|n| match n {
+            TextNode::Text(t) => t.clone(),
+            TextNode::Chord(ch) => format!("[{}]", ch),
+            TextNode::Label(l) => format!("{{{}}}", l),
+            TextNode::Newline => "\n".to_string(),
+        })
+        .collect()
+}
+
+// a song written the way `inline` shows it, "{Verse 1}\n[Am]Hello",
+// so the exporters are tested without any of the importers
+pub fn song(song_name: &str, artist: &str, text: &str) -> LyricsWithChords {
+    LyricsWithChords::new(nodes(text), artist.to_string(), song_name.to_string())
+}
+
+pub fn nodes(text: &str) -> Vec<TextNode> {
+    let mut nodes = Vec::new();
+
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let (node, length) = match c {
+            '\n' => (TextNode::Newline, 1),
+            '[' => {
+                let end = rest.find(']').unwrap();
+                (TextNode::Chord(rest[1..end].to_string()), end + 1)
+            }
+            '{' => {
+                let end = rest.find('}').unwrap();
+                (TextNode::Label(rest[1..end].to_string()), end + 1)
+            }
+            _ => {
+                let end = rest.find(['\n', '[', '{']).unwrap_or(rest.len());
+                (TextNode::Text(rest[..end].to_string()), end)
+            }
+        };
+
+        nodes.push(node);
+        rest = &rest[length..];
+    }
+
+    nodes
+}
+
+// a file of a zipped export, the epub, odt and pptx ones
+pub fn read(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+
+    content
+}
+
+// an ultimate guitar page as the site serves it,
+// the song is a json in an attribute of the .js-store element
+pub fn ultimate_guitar_page(artist: &str, song_name: &str, content: &str) -> String {
+    let data = serde_json::json!({
+        "store": {
+            "page": {
+                "data": {
+                    "tab": { "artist_name": artist, "song_name": song_name },
+                    "tab_view": { "wiki_tab": { "content": content } },
+                }
+            }
+        }
+    });
+
+    format!(
+        "<!DOCTYPE html><html><body><div class=\"js-store\" data-content=\"{}\"></div></body></html>",
+        html_escape::encode_double_quoted_attribute(&data.to_string())
+    )
+}
@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn ok(body: &str) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+// a stand-in for the song sites, every path answers with its responses in order,
+// the last one is repeated, the other paths are not found
+pub struct TestServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestServer {
+    pub async fn start(routes: Vec<(&str, Vec<Response>)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let routes: HashMap<String, Vec<Response>> = routes
+            .into_iter()
+            .map(|(path, responses)| (path.to_string(), responses))
+            .collect();
+        let routes = Arc::new(Mutex::new(routes));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let server_requests = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+
+                tokio::spawn(respond(stream, routes.clone(), server_requests.clone()));
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    // the paths (with the query) of the requests received so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn count(&self, path: &str) -> usize {
+        self.requests().iter().filter(|r| *r == path).count()
+    }
+}
+
+async fn respond(
+    mut stream: TcpStream,
+    routes: Arc<Mutex<HashMap<String, Vec<Response>>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+) {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buffer[..n]),
+        }
+    }
+
+    // GET /export.php?idpiesne=1 HTTP/1.1
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split(' ').nth(1).unwrap_or("").to_string();
+    requests.lock().unwrap().push(path.clone());
+
+    let response = match routes.lock().unwrap().get_mut(&path) {
+        Some(responses) if responses.len() > 1 => responses.remove(0),
+        Some(responses) => responses[0].clone(),
+        None => Response::status(404),
+    };
+
+    let mut head = format!(
+        "HTTP/1.1 {} Test\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in response.headers {
+        head += &format!("{}: {}\r\n", name, value);
+    }
+    head += "\r\n";
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(response.body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
@@ -0,0 +1,297 @@
+mod common;
+
+use std::{env, fs, io::Cursor, process};
+
+use spevnik::{
+    domain::{
+        core::LyricsWithChords,
+        docx_layout::{DocxLayout, Orientation, PageBreak, PageLayout, Paper},
+    },
+    export::write_docx,
+};
+use xml::reader::{EventReader, XmlEvent};
+use zip::ZipArchive;
+
+use common::{read, song};
+
+#[derive(Debug, Default)]
+struct Paragraph {
+    style: Option<String>,
+    properties: Vec<String>,
+    text: String,
+    column_break: bool,
+}
+
+// the paragraphs of the body and the children of its section properties
+// with their attributes
+struct Document {
+    paragraphs: Vec<Paragraph>,
+    section: Vec<(String, Vec<(String, String)>)>,
+    sections: usize,
+}
+
+fn songs() -> Vec<LyricsWithChords> {
+    ["First", "Second", "Third"]
+        .iter()
+        .map(|title| song(title, "Band", "[Am]one\ntwo\n\nthree"))
+        .collect()
+}
+
+fn write(name: &str, page_breaks: Option<Vec<PageBreak>>, layout: Option<PageLayout>) -> Document {
+    let path = env::temp_dir().join(format!("spevnik-{}-{}.docx", name, process::id()));
+    write_docx(
+        songs(),
+        path.to_string_lossy().to_string(),
+        None,
+        page_breaks,
+        layout,
+    )
+    .unwrap();
+
+    let mut archive = ZipArchive::new(Cursor::new(fs::read(&path).unwrap())).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    read_document(&read(&mut archive, "word/document.xml"))
+}
+
+fn read_document(document: &str) -> Document {
+    let mut paragraphs = Vec::new();
+    let mut section = Vec::new();
+    let mut sections = 0;
+    let mut path: Vec<String> = Vec::new();
+
+    for event in EventReader::new(document.as_bytes()) {
+        match event.unwrap() {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let parent = path.last().map(|p| p.as_str());
+                match (parent, name.local_name.as_str()) {
+                    (Some("body"), "p") => paragraphs.push(Paragraph::default()),
+                    (Some("body"), "sectPr") => sections += 1,
+                    (Some("pPr"), "pStyle") if path.len() == 4 => {
+                        paragraphs.last_mut().unwrap().style = attributes
+                            .iter()
+                            .find(|a| a.name.local_name == "val")
+                            .map(|a| a.value.clone())
+                    }
+                    (Some("pPr"), property) if path.len() == 4 => paragraphs
+                        .last_mut()
+                        .unwrap()
+                        .properties
+                        .push(property.to_string()),
+                    (Some("r"), "br") => {
+                        paragraphs.last_mut().unwrap().column_break = attributes
+                            .iter()
+                            .any(|a| a.name.local_name == "type" && a.value == "column")
+                    }
+                    (Some("sectPr"), element) => section.push((
+                        element.to_string(),
+                        attributes
+                            .iter()
+                            .map(|a| (a.name.local_name.clone(), a.value.clone()))
+                            .collect(),
+                    )),
+                    _ => {}
+                }
+
+                path.push(name.local_name);
+            }
+            XmlEvent::Characters(text) if path.last().is_some_and(|p| p == "t") => {
+                paragraphs.last_mut().unwrap().text += &text
+            }
+            XmlEvent::EndElement { .. } => {
+                path.pop();
+            }
+            _ => {}
+        }
+    }
+
+    Document {
+        paragraphs,
+        section,
+        sections,
+    }
+}
+
+fn properties(document: &Document, text: &str) -> Vec<String> {
+    document
+        .paragraphs
+        .iter()
+        .find(|p| p.text == text)
+        .unwrap()
+        .properties
+        .clone()
+}
+
+#[test]
+fn new_page() {
+    let document = write("new-page", None, None);
+
+    let titles: Vec<&Paragraph> = document
+        .paragraphs
+        .iter()
+        .filter(|p| p.style.as_deref() == Some("Title"))
+        .collect();
+    assert_eq!(titles.len(), 3);
+    assert_eq!(titles[0].text, "Band - First");
+
+    assert_eq!(properties(&document, "Band - First"), vec!["keepNext"]);
+    assert_eq!(
+        properties(&document, "Band - Second"),
+        vec!["keepNext", "pageBreakBefore"]
+    );
+    assert_eq!(properties(&document, "two"), Vec::<String>::new());
+
+    // the paragraphs are in the order they were written in
+    let texts: Vec<&str> = document
+        .paragraphs
+        .iter()
+        .map(|p| p.text.as_str())
+        .collect();
+    assert_eq!(
+        &texts[..6],
+        &["Band - First", "Amone", "two", "", "three", "Band - Second"]
+    );
+}
+
+#[test]
+fn continue_and_keep_together() {
+    let document = write(
+        "keep-together",
+        Some(vec![
+            PageBreak::NewPage,
+            PageBreak::Continue,
+            PageBreak::KeepTogether,
+        ]),
+        None,
+    );
+
+    assert_eq!(properties(&document, "Band - Second"), vec!["keepNext"]);
+
+    let third = document
+        .paragraphs
+        .iter()
+        .position(|p| p.text == "Band - Third")
+        .unwrap();
+    // an empty line in front of the songs sharing the page
+    assert_eq!(document.paragraphs[third - 1].text, "");
+
+    let song: Vec<Vec<String>> = document.paragraphs[third..]
+        .iter()
+        .map(|p| p.properties.clone())
+        .collect();
+    let keep_next = vec!["keepNext".to_string(), "keepLines".to_string()];
+    assert_eq!(song[..song.len() - 1], vec![keep_next; song.len() - 1]);
+    assert_eq!(song[song.len() - 1], vec!["keepLines"]);
+}
+
+#[test]
+fn new_column() {
+    let document = write(
+        "new-column",
+        Some(vec![PageBreak::NewPage, PageBreak::NewColumn]),
+        None,
+    );
+
+    let second = document
+        .paragraphs
+        .iter()
+        .find(|p| p.text == "Band - Second")
+        .unwrap();
+    assert!(second.column_break);
+    assert!(!second.properties.contains(&"pageBreakBefore".to_string()));
+}
+
+#[test]
+fn existing_paragraph_properties() {
+    let document = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Title"/><w:jc w:val="center"/></w:pPr><w:r><w:t>Title</w:t></w:r></w:p>
+<w:p><w:pPr><w:jc w:val="center"/></w:pPr><w:r><w:t>Lyrics &amp; more</w:t></w:r></w:p>
+<w:p><w:r><w:t>Last</w:t></w:r></w:p>
+<w:sectPr><w:pgSz w:w="11906" w:h="16838"/></w:sectPr>
+</w:body></w:document>"#;
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("word/document.xml", zip::write::FileOptions::default())
+        .unwrap();
+    std::io::Write::write_all(&mut zip, document.as_bytes()).unwrap();
+    let docx = zip.finish().unwrap().into_inner();
+
+    let properties = vec![
+        vec!["keepNext", "pageBreakBefore"],
+        vec!["keepLines"],
+        vec![],
+    ];
+    let docx = DocxLayout::apply(docx, &properties, &PageLayout::default()).unwrap();
+
+    let mut archive = ZipArchive::new(Cursor::new(docx)).unwrap();
+    let document = read(&mut archive, "word/document.xml");
+
+    // the style stays first, the other properties after the page breaks
+    assert!(document.contains(
+        "<w:pPr><w:pStyle w:val=\"Title\" /><w:keepNext /><w:pageBreakBefore /><w:jc w:val=\"center\" /></w:pPr>"
+    ));
+    assert!(document.contains("<w:pPr><w:keepLines /><w:jc w:val=\"center\" /></w:pPr>"));
+    assert!(document.contains("<w:p><w:r><w:t>Last</w:t></w:r></w:p>"));
+    assert!(document.contains("Lyrics &amp; more"));
+
+    let document = read_document(&document);
+    assert_eq!(document.sections, 1);
+}
+
+#[test]
+fn a5_landscape_in_two_columns() {
+    let layout = PageLayout {
+        columns: 2,
+        column_spacing_mm: 10.0,
+        paper: Paper::A5,
+        orientation: Orientation::Landscape,
+        margin_mm: 15.0,
+    };
+    let document = write("a5", None, Some(layout));
+
+    // the section properties of the fork are replaced, not added to
+    assert_eq!(document.sections, 1);
+
+    let attributes = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    };
+    assert_eq!(
+        document.section,
+        vec![
+            (
+                "pgSz".to_string(),
+                attributes(&[("w", "11906"), ("h", "8391"), ("orient", "landscape")])
+            ),
+            (
+                "pgMar".to_string(),
+                attributes(&[
+                    ("top", "850"),
+                    ("right", "850"),
+                    ("bottom", "850"),
+                    ("left", "850"),
+                    ("header", "425"),
+                    ("footer", "425"),
+                    ("gutter", "0"),
+                ])
+            ),
+            (
+                "cols".to_string(),
+                attributes(&[("num", "2"), ("space", "567")])
+            ),
+        ]
+    );
+
+    // in columns the songs are kept together instead of starting new pages
+    assert_eq!(
+        properties(&document, "Band - Second"),
+        vec!["keepNext", "keepLines"]
+    );
+    assert_eq!(properties(&document, "two"), vec!["keepNext", "keepLines"]);
+    assert_eq!(properties(&document, "three"), vec!["keepLines"]);
+}
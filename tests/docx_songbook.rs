@@ -0,0 +1,100 @@
+mod common;
+
+use docx::{
+    document::{Paragraph, Run},
+    formatting::{CharacterProperty, ParagraphProperty, VerticalAlignment},
+    Docx,
+};
+use spevnik::domain::{core::TITLE_STYLE, docx_songbook::DocxSongbook};
+
+use common::{inline, song};
+
+// a title paragraph as `render_docx` writes it
+fn title(text: &str) -> Paragraph<'static> {
+    Paragraph::default()
+        .property(ParagraphProperty::default().style_id(TITLE_STYLE))
+        .push(
+            Run::default()
+                .push_text(text.to_string())
+                .property(CharacterProperty::default().bold(true)),
+        )
+}
+
+fn songbook(paragraphs: Vec<Paragraph<'static>>) -> Docx<'static> {
+    let mut document = Docx::default();
+    for paragraph in paragraphs {
+        document.document.push(paragraph);
+    }
+
+    document
+}
+
+#[test]
+fn round_trip() {
+    let songs = [
+        song(
+            "First",
+            "Band",
+            "[Am]Hello [C]darkness\n\n{®:}\n[F]la [G]la",
+        ),
+        song("Second", "Other Band", "[D] [A]\nText"),
+    ];
+
+    let mut paragraphs = vec![Paragraph::default().push(Run::default().push_text("Contents"))];
+    for song in songs.iter() {
+        paragraphs.extend(song.clone().render_docx());
+    }
+    let read_back = DocxSongbook::parse(&songbook(paragraphs)).unwrap();
+
+    assert_eq!(read_back.len(), 2);
+    for (song, read_back) in songs.iter().zip(read_back.iter()) {
+        assert_eq!(read_back.artist, song.artist);
+        assert_eq!(read_back.song_name, song.song_name);
+        assert_eq!(inline(read_back), inline(song));
+    }
+}
+
+#[test]
+fn split_runs() {
+    let chord = || {
+        CharacterProperty::default()
+            .bold(true)
+            .vertical_alignment(VerticalAlignment::superscript())
+    };
+
+    let paragraphs = vec![
+        title("Band - Song"),
+        Paragraph::default()
+            .push(Run::default().push_text("Am C").property(chord()))
+            .push(Run::default().push_text("Hel"))
+            .push(Run::default().push_text("lo")),
+    ];
+
+    let songs = DocxSongbook::parse(&songbook(paragraphs)).unwrap();
+    assert_eq!(inline(&songs[0]), "[Am] [C]Hello");
+}
+
+#[test]
+fn bold_lyrics_with_a_dash() {
+    let paragraphs = vec![
+        title("Band - Song"),
+        Paragraph::default().push(Run::default().push_text("Hello")),
+        Paragraph::default().push(
+            Run::default()
+                .push_text("Sing - sing along")
+                .property(CharacterProperty::default().bold(true)),
+        ),
+        Paragraph::default().push(Run::default().push_text("la la")),
+    ];
+
+    let songs = DocxSongbook::parse(&songbook(paragraphs)).unwrap();
+    assert_eq!(songs.len(), 1);
+    assert_eq!(inline(&songs[0]), "Hello\n{Sing - sing along}\nla la");
+}
+
+#[test]
+fn no_songs() {
+    let paragraphs = vec![Paragraph::default().push(Run::default().push_text("Band - Song"))];
+
+    assert!(DocxSongbook::parse(&songbook(paragraphs)).is_err());
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::io::Cursor;
+
+use spevnik::domain::epub::Epub;
+use zip::{CompressionMethod, ZipArchive};
+
+use common::{read, song};
+
+fn render() -> ZipArchive<Cursor<Vec<u8>>> {
+    let songs = [
+        song("Zuzana", "Band", "[Am]Hello"),
+        song("Anna", "Other", "Text"),
+        song("Mária", "band", "Text"),
+    ];
+
+    ZipArchive::new(Cursor::new(
+        Epub::render(&songs, None, "Camp & Fire").unwrap(),
+    ))
+    .unwrap()
+}
+
+#[test]
+fn mimetype_first_and_stored() {
+    let mut archive = render();
+
+    let mimetype = archive.by_index(0).unwrap();
+    assert_eq!(mimetype.name(), "mimetype");
+    assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+    drop(mimetype);
+
+    assert_eq!(read(&mut archive, "mimetype"), "application/epub+zip");
+    assert!(
+        read(&mut archive, "META-INF/container.xml").contains("full-path=\"OEBPS/content.opf\"")
+    );
+}
+
+#[test]
+fn pages() {
+    let mut archive = render();
+
+    let package = read(&mut archive, "OEBPS/content.opf");
+    assert!(package.contains("<dc:title>Camp &amp; Fire</dc:title>"));
+    assert!(package.contains(
+        "<itemref idref=\"nav\"/>\n<itemref idref=\"song-1\"/>\n<itemref idref=\"song-2\"/>\n<itemref idref=\"song-3\"/>"
+    ));
+
+    let page = read(&mut archive, "OEBPS/song-1.xhtml");
+    assert!(page.contains("<title>Band - Zuzana</title>"));
+    assert!(page.contains("<span class=\"chord\">Am</span>"));
+    assert_eq!(
+        read(&mut archive, "OEBPS/style.css"),
+        spevnik::domain::html::Html::stylesheet()
+    );
+}
+
+#[test]
+fn navigation() {
+    let mut archive = render();
+    let navigation = read(&mut archive, "OEBPS/nav.xhtml");
+
+    // sorted by the titles
+    assert!(navigation.contains(
+        "<li><a href=\"song-2.xhtml\">Anna</a></li>\n<li><a href=\"song-3.xhtml\">Mária</a></li>\n<li><a href=\"song-1.xhtml\">Zuzana</a></li>"
+    ));
+    // the artists are grouped ignoring the case, under the name of their first song
+    assert!(navigation.contains(
+        "<li><span>band</span>\n<ol>\n<li><a href=\"song-3.xhtml\">Mária</a></li>\n<li><a href=\"song-1.xhtml\">Zuzana</a></li>\n</ol>\n</li>\n<li><span>Other</span>"
+    ));
+}
+
+#[test]
+fn same_songs_same_identifier() {
+    let identifier = |archive: &mut ZipArchive<Cursor<Vec<u8>>>| {
+        let package = read(archive, "OEBPS/content.opf");
+        let (_, rest) = package
+            .split_once("<dc:identifier id=\"identifier\">")
+            .unwrap();
+        rest.split_once("<").unwrap().0.to_string()
+    };
+
+    assert_eq!(identifier(&mut render()), identifier(&mut render()));
+}
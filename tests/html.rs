@@ -0,0 +1,91 @@
+mod common;
+
+use spevnik::domain::html::{Html, HtmlOptions};
+
+use common::song;
+
+#[test]
+fn chords_above_words() {
+    let html = Html::render_song(&song("", "", "[Am]Hello dark[C]ness my [G]"), None);
+
+    assert!(html.contains(
+        "<div class=\"line\">\
+         <span class=\"chunk\"><span class=\"chord\">Am</span><span class=\"lyrics\">Hello</span></span> dark\
+         <span class=\"chunk\"><span class=\"chord\">C</span><span class=\"lyrics\">ness</span></span> my \
+         <span class=\"chunk\"><span class=\"chord\">G</span><span class=\"lyrics\"> </span></span>\
+         </div>"
+    ));
+}
+
+#[test]
+fn sections() {
+    let html = Html::render_song(
+        &song("", "", "{Verse 1}\nHello\n\n{®:}\n[F]la la\n\n{®:}"),
+        None,
+    );
+
+    assert!(html.contains(
+        "<div class=\"section verse\">\n<div class=\"line\"><span class=\"label\">Verse 1</span>Hello</div>\n</div>"
+    ));
+    assert!(html.contains(
+        "<div class=\"section chorus\">\n<div class=\"line\"><span class=\"label\">®:</span>"
+    ));
+    // the repeated chorus is only its label
+    assert!(html.contains(
+        "<div class=\"section chorus repeat\">\n<div class=\"line\"><span class=\"label\">®:</span></div>\n</div>"
+    ));
+}
+
+#[test]
+fn metadata() {
+    let mut song = song("Song", "Band", "Text");
+    song.metadata.lyricist = Some("Words".to_string());
+    song.metadata.key = Some("G".to_string());
+    song.metadata.capo = Some(2);
+
+    let html = Html::render_song(&song, None);
+
+    assert!(html.contains("<h1>Band - Song</h1>"));
+    assert!(html.contains("<p class=\"metadata\">Words: Words · Key: G · Capo: 2</p>"));
+}
+
+#[test]
+fn escaping() {
+    let html = Html::render_song(&song("<Song>", "A & B", "[A<]x <b>"), None);
+
+    assert!(html.contains("<h1>A &amp; B - &lt;Song&gt;</h1>"));
+    assert!(html.contains(
+        "<span class=\"chord\">A&lt;</span><span class=\"lyrics\">x</span></span> &lt;b&gt;"
+    ));
+    assert!(!html.contains("<b>"));
+}
+
+#[test]
+fn songbook() {
+    let songs = [song("First", "", "Text"), song("Second", "Band", "Text")];
+    let options = HtmlOptions {
+        title: Some("Camp".to_string()),
+        ..Default::default()
+    };
+
+    let html = Html::render_songbook(&songs, None, &options);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Camp</title>"));
+    assert!(html.contains(Html::stylesheet()));
+    assert!(html.contains(
+        "<ol>\n<li><a href=\"#song-1\">First</a></li>\n<li><a href=\"#song-2\">Band - Second</a></li>\n</ol>"
+    ));
+    assert!(html.contains("<article class=\"song\" id=\"song-2\">"));
+
+    // a single song or a songbook without the contents
+    let html = Html::render_songbook(&songs[..1], None, &options);
+    assert!(!html.contains("class=\"toc\""));
+
+    let options = HtmlOptions {
+        table_of_contents: false,
+        ..Default::default()
+    };
+    let html = Html::render_songbook(&songs, None, &options);
+    assert!(!html.contains("class=\"toc\""));
+    assert!(html.contains("<title>Songbook</title>"));
+}
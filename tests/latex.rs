@@ -0,0 +1,65 @@
+mod common;
+
+use spevnik::domain::{core::TextNode, latex::Latex};
+
+use common::song;
+
+#[test]
+fn render_song() {
+    let mut song = song(
+        "Song",
+        "Band",
+        "{Verse 1}\n[Am]Hello dark[C#m]ness\n\n{®:}\n[F]la la\n\n{Bridge}\n[Bb]bridge\n\n{®:}",
+    );
+    song.metadata.capo = Some(2);
+
+    assert_eq!(
+        Latex::render_song(&song, None),
+        "\\beginsong{Song}[by={Band}]\n\\capo{2}\n\
+         \\beginverse\n\\[Am]Hello dark\\[C#m]ness\n\\endverse\n\
+         \\beginchorus\n\\[F]la la\n\\endchorus\n\
+         \\beginverse*\n\\textnote{Bridge}\n\\[Bb]bridge\n\\endverse\n\
+         \\textnote{\\textregistered{}:}\n\\endsong\n"
+    );
+}
+
+#[test]
+fn without_artist() {
+    assert_eq!(
+        Latex::render_song(&song("Song", "", "Text"), None),
+        "\\beginsong{Song}\n\\beginverse\nText\n\\endverse\n\\endsong\n"
+    );
+}
+
+#[test]
+fn escaping() {
+    let mut song = song("100% & more", "", "[A&]");
+    song.text
+        .push(TextNode::Text("a_b {x} #1 ~ ^ \\".to_string()));
+
+    let latex = Latex::render_song(&song, None);
+    assert!(latex.starts_with("\\beginsong{100\\% \\& more}"));
+    assert!(latex.contains(
+        "\\[A\\&]a\\_b \\{x\\} \\#1 \\textasciitilde{} \\textasciicircum{} \\textbackslash{}"
+    ));
+}
+
+#[test]
+fn diacritics() {
+    let latex = Latex::render_song(&song("", "", "Ľúbosť, čín, „ahoj“ – ô…"), None);
+
+    assert!(latex.contains("\\v{L}\\'{u}bos\\v{t}, \\v{c}\\'{\\i}n, ,,ahoj`` -- \\^{o}\\ldots{}"));
+}
+
+#[test]
+fn songbook() {
+    let songs = [song("First", "", "Text"), song("Second", "", "Text")];
+
+    let latex = Latex::render_songbook(&songs, None);
+    assert!(latex.starts_with("\\documentclass{article}\n"));
+    assert!(latex.contains("\\usepackage[chorded]{songs}"));
+    assert!(latex.contains(
+        "\\begin{songs}{titleidx,authidx}\n\\beginsong{First}\n\\beginverse\nText\n\\endverse\n\\endsong\n\n\\beginsong{Second}"
+    ));
+    assert!(latex.ends_with("\\end{songs}\n\\end{document}\n"));
+}
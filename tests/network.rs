@@ -0,0 +1,248 @@
+mod common;
+
+use std::{path::PathBuf, time::Duration};
+
+use spevnik::{
+    cache::PageCache,
+    export::fetch_song,
+    network::{Network, NetworkPolicy},
+};
+
+use common::{
+    fixture, inline,
+    server::{Response, TestServer},
+    ultimate_guitar_page,
+};
+
+const SONG_URL: &str = "https://supermusic.cz/skupina.php?idpiesne=198707&sid=";
+const SONG_PATH: &str = "/skupina.php?idpiesne=198707&sid=";
+const EXPORT_PATH: &str = "/export.php?idpiesne=198707&stiahni=1&typ=TXT&sid=";
+
+const TAB_URL: &str =
+    "https://tabs.ultimate-guitar.com/tab/simon-garfunkel/the-sound-of-silence-chords-1";
+const TAB_PATH: &str = "/tab/simon-garfunkel/the-sound-of-silence-chords-1";
+
+// no waiting between the requests, so the tests stay fast
+fn policy() -> NetworkPolicy {
+    NetworkPolicy {
+        retries: 2,
+        initial_backoff_ms: 10,
+        max_backoff_ms: 50,
+        min_request_interval_ms: 0,
+        timeout_ms: 5000,
+        ..NetworkPolicy::default()
+    }
+}
+
+fn network(server: &TestServer) -> Network {
+    Network::from_policy(policy())
+        .unwrap()
+        .with_base_url("supermusic.cz", &server.base_url)
+        .with_base_url("tabs.ultimate-guitar.com", &server.base_url)
+}
+
+fn cache_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "spevnik-test-cache-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&directory);
+
+    directory
+}
+
+#[tokio::test]
+async fn supermusic_text_export() {
+    let server = TestServer::start(vec![
+        (SONG_PATH, vec![Response::ok(&fixture("supermusic.html"))]),
+        (EXPORT_PATH, vec![Response::ok(&fixture("supermusic.txt"))]),
+    ])
+    .await;
+
+    let song = fetch_song(&network(&server), SONG_URL.to_string(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(song.artist, "Ľudové");
+    assert_eq!(song.song_name, "Hej, sokoly");
+    // only the text export has the whole song
+    assert_eq!(
+        inline(&song),
+        "{1.} [Am]Hej, tam gdzieś z nad [E]czarnej wody\n\
+         [E]Siada na koń kozak [Am]młody,\n\
+         Czule żegna się z dziewczyną,\n\
+         Jeszcze czulej z Ukrainą.\n\
+         \n\
+         {®:} [C]Hej, hej, hej sokoły,\n\
+         Omijajcie góry, lasy, doły."
+    );
+    // the metadata are only on the song page
+    assert_eq!(song.metadata.capo, Some(2));
+
+    assert_eq!(server.count(SONG_PATH), 1);
+    assert_eq!(server.count(EXPORT_PATH), 1);
+}
+
+#[tokio::test]
+async fn supermusic_html_fallback() {
+    // the export is missing, or it is a page saying the export is disabled
+    for export in [
+        Response::status(404),
+        Response::ok("<html><body>Export nie je povolený.</body></html>"),
+    ] {
+        let server = TestServer::start(vec![
+            (SONG_PATH, vec![Response::ok(&fixture("supermusic.html"))]),
+            (EXPORT_PATH, vec![export]),
+        ])
+        .await;
+
+        let song = fetch_song(&network(&server), SONG_URL.to_string(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            inline(&song).lines().nth(1),
+            Some("[E]Siada na koń kozak [Am]młody")
+        );
+        assert_eq!(server.count(EXPORT_PATH), 1);
+    }
+}
+
+#[tokio::test]
+async fn retries_after_too_many_requests() {
+    let page = ultimate_guitar_page(
+        "Simon & Garfunkel",
+        "The Sound of Silence",
+        "[ch]Am[/ch]\r\nHello",
+    );
+    let server = TestServer::start(vec![(
+        TAB_PATH,
+        vec![
+            Response::status(429).header("Retry-After", "0"),
+            Response::status(503),
+            Response::ok(&page),
+        ],
+    )])
+    .await;
+
+    let song = fetch_song(&network(&server), TAB_URL.to_string(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(inline(&song), "[Am]Hello");
+    assert_eq!(server.count(TAB_PATH), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_the_retries() {
+    let server = TestServer::start(vec![(
+        TAB_PATH,
+        vec![Response::status(429).header("Retry-After", "0")],
+    )])
+    .await;
+
+    let error = fetch_song(&network(&server), TAB_URL.to_string(), None)
+        .await
+        .unwrap_err();
+
+    assert!(error.to_string().contains("429"), "{}", error);
+    assert_eq!(server.count(TAB_PATH), 1 + policy().retries as usize);
+}
+
+#[tokio::test]
+async fn caps_the_retry_after_wait() {
+    let page = ultimate_guitar_page("Artist", "Song", "[ch]Am[/ch]\r\nHello");
+    let server = TestServer::start(vec![(
+        TAB_PATH,
+        vec![
+            Response::status(503).header("Retry-After", "86400"),
+            Response::ok(&page),
+        ],
+    )])
+    .await;
+
+    let song = tokio::time::timeout(
+        Duration::from_secs(5),
+        fetch_song(&network(&server), TAB_URL.to_string(), None),
+    )
+    .await
+    .expect("waited for the whole Retry-After")
+    .unwrap();
+
+    assert_eq!(inline(&song), "[Am]Hello");
+    assert_eq!(server.count(TAB_PATH), 2);
+}
+
+#[tokio::test]
+async fn redirects_which_are_not_followed() {
+    let server = TestServer::start(vec![
+        (TAB_PATH, vec![Response::status(304)]),
+        (SONG_PATH, vec![Response::status(300)]),
+    ])
+    .await;
+
+    let error = fetch_song(&network(&server), TAB_URL.to_string(), None)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("304"), "{}", error);
+    assert_eq!(server.count(TAB_PATH), 1);
+
+    let error = fetch_song(&network(&server), SONG_URL.to_string(), None)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("300"), "{}", error);
+}
+
+#[tokio::test]
+async fn offline_cache_miss() {
+    let server = TestServer::start(vec![(
+        TAB_PATH,
+        vec![Response::ok(&fixture("ultimate_guitar.html"))],
+    )])
+    .await;
+
+    let cache = PageCache::new(cache_directory("offline"), Duration::from_secs(60));
+    let network = network(&server).with_cache(cache.clone());
+
+    network.set_offline(true);
+    let error = fetch_song(&network, TAB_URL.to_string(), None)
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("is not cached and offline mode is enabled"),
+        "{}",
+        error
+    );
+    assert_eq!(server.requests().len(), 0);
+
+    // once downloaded, the page is there without the network
+    network.set_offline(false);
+    let online = fetch_song(&network, TAB_URL.to_string(), None)
+        .await
+        .unwrap();
+
+    network.set_offline(true);
+    let offline = fetch_song(&network, TAB_URL.to_string(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(inline(&offline), inline(&online));
+    assert_eq!(server.count(TAB_PATH), 1);
+
+    cache.clear().await.unwrap();
+}
+
+#[tokio::test]
+async fn offline_without_cache() {
+    let server = TestServer::start(vec![]).await;
+    let network = network(&server);
+    network.set_offline(true);
+
+    assert!(fetch_song(&network, TAB_URL.to_string(), None)
+        .await
+        .is_err());
+    assert_eq!(server.requests().len(), 0);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::io::Cursor;
+
+use spevnik::domain::{core::LyricsWithChords, odt::Odt};
+use zip::{CompressionMethod, ZipArchive};
+
+use common::{read, song};
+
+fn content(songs: &[LyricsWithChords]) -> String {
+    let mut archive = ZipArchive::new(Cursor::new(Odt::render(songs).unwrap())).unwrap();
+
+    let mimetype = archive.by_index(0).unwrap();
+    assert_eq!(mimetype.name(), "mimetype");
+    assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+    drop(mimetype);
+
+    read(&mut archive, "content.xml")
+}
+
+#[test]
+fn songs() {
+    let content = content(&[
+        song("First", "Band", "{®:}\n[Am]Hello [C]darkness"),
+        song("Second", "", "Text"),
+    ]);
+
+    assert!(content.contains(
+        "<text:p text:style-name=\"Song_20_Title\">Band - First</text:p>\
+         <text:p text:style-name=\"Lyrics\"><text:span text:style-name=\"Label\">®:</text:span></text:p>\
+         <text:p text:style-name=\"Lyrics\"><text:span text:style-name=\"Chord\">Am</text:span>Hello<text:s text:c=\"1\" />\
+         <text:span text:style-name=\"Chord\">C</text:span>darkness</text:p>\
+         <text:p text:style-name=\"Song_20_Title_20_New_20_Page\">Second</text:p>"
+    ));
+}
+
+#[test]
+fn spaces() {
+    let content = content(&[song("Song", "", "  two  spaces & <more>   ")]);
+
+    assert!(content.contains(
+        "<text:p text:style-name=\"Lyrics\"><text:s text:c=\"2\" />two <text:s text:c=\"1\" />spaces &amp; &lt;more&gt;<text:s text:c=\"3\" /></text:p>"
+    ));
+}
+
+#[test]
+fn spaces_before_tabs() {
+    let content = content(&[song("Song", "", "a  \tb\t c")]);
+
+    assert!(content.contains(
+        "<text:p text:style-name=\"Lyrics\">a <text:s text:c=\"1\" /><text:tab />b<text:tab /><text:s text:c=\"1\" />c</text:p>"
+    ));
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use spevnik::domain::{chordpro::ChordPro, core::LyricsWithChords, onsong::OnSong};
+
+use common::inline;
+
+const SONG: &str = "Amazing Grace
+John Newton
+Key: G
+Capo: 2
+Tempo: 80
+
+Verse 1:
+[G]Amazing grace, how [C]sweet the sound
+That saved a wretch like [D7]me
+
+Chorus:
+[Em]I once was lost
+
+Tag:
+[G]me
+";
+
+#[test]
+fn parse() {
+    let song = OnSong::parse(SONG, None).unwrap();
+
+    assert_eq!(song.song_name, "Amazing Grace");
+    assert_eq!(song.artist, "John Newton");
+    assert_eq!(song.metadata.key.as_deref(), Some("G"));
+    assert_eq!(song.metadata.capo, Some(2));
+    assert_eq!(
+        inline(&song),
+        "{Verse 1}\n[G]Amazing grace, how [C]sweet the sound\nThat saved a wretch like [D7]me\n\n\
+         {®:}\n[Em]I once was lost\n\n{Tag}\n[G]me"
+    );
+}
+
+#[test]
+fn parse_tagged_header() {
+    let song = OnSong::parse(
+        "\nTitle: Song\nArtist: Band\nComposer: Composer\nLyricist: Lyricist\nAlbum: Album\n\nText",
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(song.song_name, "Song");
+    assert_eq!(song.artist, "Band");
+    assert_eq!(song.metadata.composer.as_deref(), Some("Composer"));
+    assert_eq!(song.metadata.lyricist.as_deref(), Some("Lyricist"));
+    assert_eq!(song.metadata.album.as_deref(), Some("Album"));
+    assert_eq!(inline(&song), "Text");
+}
+
+#[test]
+fn parse_without_artist() {
+    let song = OnSong::parse("Song\n\n[C]Text", None).unwrap();
+
+    assert_eq!(song.song_name, "Song");
+    assert_eq!(song.artist, "");
+    assert_eq!(inline(&song), "[C]Text");
+}
+
+fn song(song_name: &str, artist: &str) -> LyricsWithChords {
+    let mut song = ChordPro::parse(
+        "[Am]First [C]line\n[G]second line\n\n{soc}\n[F]Chorus\n{eoc}\n\n{c: Bridge}\n[E]Bridge",
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    song.song_name = song_name.to_string();
+    song.artist = artist.to_string();
+
+    song
+}
+
+fn assert_round_trip(song: &LyricsWithChords) {
+    let read_back = OnSong::parse(&OnSong::render(song, None), None).unwrap();
+
+    assert_eq!(read_back.song_name, song.song_name);
+    assert_eq!(read_back.artist, song.artist);
+    assert_eq!(read_back.metadata, song.metadata);
+    assert_eq!(inline(&read_back), inline(song));
+}
+
+#[test]
+fn round_trip() {
+    let mut song = song("Song", "Band");
+    song.metadata.composer = Some("Composer".to_string());
+    song.metadata.key = Some("Am".to_string());
+    song.metadata.capo = Some(3);
+
+    assert_round_trip(&song);
+}
+
+#[test]
+fn round_trip_without_title() {
+    assert_round_trip(&song("", "Band"));
+    assert_round_trip(&song("", ""));
+}
+
+#[test]
+fn round_trip_without_artist() {
+    assert_round_trip(&song("Song", ""));
+}
+
+#[test]
+fn render() {
+    assert_eq!(
+        OnSong::render(&song("Song", "Band"), None),
+        "Song\nBand\n\n[Am]First [C]line\n[G]second line\n\nChorus:\n[F]Chorus\n\nBridge:\n[E]Bridge\n"
+    );
+}
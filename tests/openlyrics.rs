@@ -0,0 +1,93 @@
+mod common;
+
+use spevnik::domain::{chordpro::ChordPro, core::LyricsWithChords, openlyrics::OpenLyrics};
+
+use common::{fixture, inline};
+
+#[test]
+fn parse() {
+    let song = OpenLyrics::parse(&fixture("openlyrics.xml"), None).unwrap();
+
+    assert_eq!(song.song_name, "Amazing Grace");
+    assert_eq!(song.artist, "John Newton");
+    assert_eq!(song.metadata.lyricist.as_deref(), Some("John Newton"));
+    assert_eq!(song.metadata.composer.as_deref(), Some("Traditional"));
+    assert_eq!(song.metadata.album.as_deref(), Some("Hymns"));
+    assert_eq!(song.metadata.key.as_deref(), Some("G"));
+    assert_eq!(song.metadata.capo, Some(2));
+
+    // the lines are broken by <br/>, the newlines in the file are spaces
+    assert_eq!(
+        inline(&song),
+        "[G]Amazing grace, how sweet the [C]sound\n\
+         That saved a wretch like [D7]me\n\
+         \n\
+         {®:} [Em/D]I once was lost,\n\
+         but now am found\n\
+         \n\
+         'Twas grace that taught\n\
+         my heart to fear\n\
+         \n\
+         {®:}"
+    );
+}
+
+#[test]
+fn without_verse_order() {
+    let input = fixture("openlyrics.xml").replace("<verseOrder>v1 c1 v2 c1</verseOrder>", "");
+    let song = OpenLyrics::parse(&input, None).unwrap();
+
+    assert!(inline(&song).ends_with("'Twas grace that taught\nmy heart to fear"));
+}
+
+#[test]
+fn missing_verses() {
+    assert!(OpenLyrics::parse(
+        "<song xmlns=\"http://openlyrics.info/namespace/2009/song\"><properties><titles><title>x</title></titles></properties></song>",
+        None
+    )
+    .is_err());
+}
+
+fn round_trip(song: &LyricsWithChords) -> LyricsWithChords {
+    OpenLyrics::parse(&OpenLyrics::render(song, None).unwrap(), None).unwrap()
+}
+
+#[test]
+fn export_import() {
+    let mut song = ChordPro::parse(
+        "{title: Song}\n{artist: Artist}\n\
+         [Am]First verse [C]line\n[G]second line\n\n\
+         {soc}\n[F]Chorus   with  [G]spaces\n{eoc}\n\n\
+         Second verse\n\n\
+         {chorus}",
+        None,
+    )
+    .unwrap()
+    .remove(0);
+    song.metadata.composer = Some("Composer".to_string());
+    song.metadata.key = Some("Am".to_string());
+    song.metadata.capo = Some(4);
+
+    let read_back = round_trip(&song);
+
+    // the label is read back in the first line of its section
+    assert_eq!(
+        inline(&read_back),
+        inline(&song).replace("{®:}\n[F]", "{®:} [F]")
+    );
+    assert_eq!(read_back.song_name, song.song_name);
+    assert_eq!(read_back.artist, song.artist);
+    assert_eq!(read_back.metadata, song.metadata);
+}
+
+#[test]
+fn export_repeated_chorus() {
+    let song = OpenLyrics::parse(&fixture("openlyrics.xml"), None).unwrap();
+    let rendered = OpenLyrics::render(&song, None).unwrap();
+
+    // the chorus is written once and repeated by the verse order
+    assert_eq!(rendered.matches("<verse name=\"c1\">").count(), 1);
+    assert!(rendered.contains("<verseOrder>v1 c1 v2 c1</verseOrder>"));
+    assert_eq!(inline(&round_trip(&song)), inline(&song));
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use spevnik::domain::{
+    core::LyricsWithChords,
+    pdf::{PdfFonts, PdfOptions, PdfSongbook},
+};
+
+use common::song;
+
+fn fonts() -> PdfFonts {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("RobotoMedium.ttf");
+
+    PdfFonts {
+        regular: fs::read(path).unwrap(),
+        bold: None,
+    }
+}
+
+fn render(songs: &[LyricsWithChords], options: &PdfOptions) -> String {
+    let pdf = PdfSongbook::render(songs, &fonts(), options).unwrap();
+    assert!(pdf.starts_with(b"%PDF"));
+
+    String::from_utf8_lossy(&pdf).to_string()
+}
+
+fn page_count(pdf: &str) -> usize {
+    let (_, count) = pdf.split_once("/Type/Pages/Count ").unwrap();
+    let digits: String = count.chars().take_while(|c| c.is_ascii_digit()).collect();
+
+    digits.parse().unwrap()
+}
+
+#[test]
+fn every_song_on_a_new_page() {
+    let songs = [
+        song("First", "", "[Am]Hello [C]darkness"),
+        song("Second", "", "[G]my old friend"),
+    ];
+
+    assert_eq!(page_count(&render(&songs, &PdfOptions::default())), 2);
+}
+
+#[test]
+fn long_songs_continue_on_the_next_page() {
+    let verse = "[Am]Hello darkness my old [C]friend\n".repeat(4);
+    let text = vec![verse; 20].join("\n");
+
+    assert!(page_count(&render(&[song("Long", "", &text)], &PdfOptions::default())) > 1);
+}
+
+#[test]
+fn long_lines_are_wrapped() {
+    let options = PdfOptions {
+        page_height_mm: 60.0,
+        margin_mm: 10.0,
+        ..Default::default()
+    };
+
+    // one line fits on a page, the same line wrapped into many rows does not
+    let line = "[Am]Hello darkness my old [C]friend ".repeat(2);
+    assert_eq!(page_count(&render(&[song("", "", &line)], &options)), 1);
+
+    let line = "[Am]Hello darkness my old [C]friend ".repeat(20);
+    assert!(page_count(&render(&[song("", "", &line)], &options)) > 1);
+}
+
+#[test]
+fn slovak_letters_are_embedded() {
+    let pdf = render(
+        &[song("Ľaľa", "", "[Am]ľúbostná pieseň, ĺ, ŕ")],
+        &PdfOptions::default(),
+    );
+
+    // the glyphs are mapped back to the letters, so the text can be copied
+    for letter in ['ľ', 'ĺ', 'ŕ', 'ú', 'ň'] {
+        assert!(
+            pdf.contains(&format!("> <{:04x}>", letter as u32)),
+            "{}",
+            letter
+        );
+    }
+}
+
+#[test]
+fn invalid_font() {
+    let fonts = PdfFonts {
+        regular: b"not a font".to_vec(),
+        bold: None,
+    };
+
+    assert!(PdfSongbook::render(&[song("", "", "text")], &fonts, &PdfOptions::default()).is_err());
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use std::collections::HashMap;
+
+use spevnik::domain::{
+    alignment::AlignmentGuess,
+    core::{Options, SectionKind},
+    plain_text::PlainText,
+};
+
+use common::inline;
+
+fn parse(text: &str) -> String {
+    inline(&PlainText::parse(text, None).unwrap())
+}
+
+#[test]
+fn title() {
+    let song = PlainText::parse(
+        "\n\nSimon & Garfunkel - The Sound of Silence\n\nHello",
+        None,
+    )
+    .unwrap();
+    assert_eq!(song.artist, "Simon & Garfunkel");
+    assert_eq!(song.song_name, "The Sound of Silence");
+    assert_eq!(inline(&song), "Hello");
+
+    let song = PlainText::parse("The Sound of Silence\n\nHello", None).unwrap();
+    assert_eq!(song.artist, "");
+    assert_eq!(song.song_name, "The Sound of Silence");
+
+    // without the blank line the first line is lyrics
+    let song = PlainText::parse("Hello darkness\nmy old friend", None).unwrap();
+    assert_eq!(song.song_name, "");
+    assert_eq!(inline(&song), "Hello darkness\nmy old friend");
+}
+
+#[test]
+fn chords_above_lyrics() {
+    assert_eq!(
+        parse("Am      C\r\nHello darkness\r\nG         D/F#\r\nmy old friend"),
+        "[Am]Hello da[C]rkness\n[G]my old fri[D/F#]end"
+    );
+}
+
+#[test]
+fn tabs() {
+    assert_eq!(parse("\tC\nword\tword"), "word    [C]word");
+}
+
+#[test]
+fn headings() {
+    assert_eq!(
+        parse("[Verse 1]\nC\nHello\n\nChorus:\nG\nla\nBridge\nbr\n[Solo]\nAm"),
+        "{Verse 1}\n[C]Hello\n\n{®:}\n[G]la\n\n{Bridge}\nbr\n\n{Solo}\n[Am]"
+    );
+}
+
+#[test]
+fn lyrics_ending_with_a_colon() {
+    let (song, _) = round_trip("And she said:\nHello\nTag:\nla");
+    assert_eq!(song, "And she said:\nHello\nTag:\nla");
+
+    // the lyrics of a song from another format
+    let song = common::song("Song", "", "[Am]And she said:\nHello");
+    let read_back = PlainText::parse(&PlainText::render(&song), None).unwrap();
+    assert_eq!(read_back.text, song.text);
+
+    assert_eq!(
+        parse("Verse 2:\nla\n[Tag]\nlo"),
+        "{Verse 2:}\nla\n\n{Tag}\nlo"
+    );
+}
+
+#[test]
+fn markers() {
+    assert_eq!(
+        parse("1. Hello darkness\nmy old friend\nR: la la\n\nR:\nla\n2.\nlo"),
+        "{1.} Hello darkness\nmy old friend\n\n{®:} la la\n\n{®:}\nla\n\n{2.}\nlo"
+    );
+}
+
+#[test]
+fn marker_before_chords() {
+    // the marker does not move the chords
+    assert_eq!(
+        parse("   Am      C\nR: Hello darkness"),
+        "{®:} [Am]Hello da[C]rkness"
+    );
+    assert_eq!(parse("Intro: C G | Am F"), "{Intro:} [C] [G] | [Am] [F]");
+}
+
+#[test]
+fn mapped_labels() {
+    let options = Options {
+        section_labels: HashMap::from([(SectionKind::Verse, Some("{n}.".to_string()))]),
+        ..Default::default()
+    };
+
+    let song = PlainText::parse("[Verse 1]\nHello\n\n2. darkness", Some(options)).unwrap();
+    assert_eq!(inline(&song), "{1.}\nHello\n\n{2.} darkness");
+}
+
+#[test]
+fn chords_without_lyrics() {
+    assert_eq!(
+        parse("C  G\n\nAm    F |  x2\nC\n\nHello"),
+        "[C] [G]\n\n[Am] [F] | x2\n[C]\n\nHello"
+    );
+}
+
+#[test]
+fn lyrics_are_not_chords() {
+    assert_eq!(parse("A Bad Day\nAm\nA Dream"), "A Bad Day\n[Am]A Dream");
+}
+
+#[test]
+fn guesses() {
+    let (song, guesses) =
+        PlainText::parse_with_guesses("C   G      D\nHello\n\nF\nla", None).unwrap();
+
+    assert_eq!(inline(&song), "[C]Hell[G]o [D]\n\n[F]la");
+    assert_eq!(guesses.len(), 1);
+    assert!(matches!(
+        &guesses[0],
+        (0, AlignmentGuess::Overflow { chord, column: 11 }) if chord == "D"
+    ));
+}
+
+fn round_trip(text: &str) -> (String, String) {
+    let song = PlainText::parse(text, None).unwrap();
+    let rendered = PlainText::render(&song);
+    let read_back = PlainText::parse(&rendered, None).unwrap();
+
+    assert_eq!(read_back.artist, song.artist, "{}", rendered);
+    assert_eq!(read_back.song_name, song.song_name, "{}", rendered);
+    assert_eq!(read_back.text, song.text, "{}", rendered);
+
+    (inline(&song), rendered)
+}
+
+#[test]
+fn render() {
+    let (_, rendered) =
+        round_trip("Band - Song\n\n[Verse 1]\nAm      C\nHello darkness\n\nChorus:\nG\nla la");
+
+    assert_eq!(
+        rendered,
+        "Band - Song\n\n[Verse 1]\nAm      C\nHello darkness\n\n[®:]\nG\nla la\n"
+    );
+}
+
+#[test]
+fn render_long_chords() {
+    // the lyrics are pushed apart with spaces, inside of a word the chord
+    // is two columns after the previous one
+    let (song, rendered) = round_trip("Cmaj7  Gsus4\nHel    lo");
+    assert_eq!(song, "[Cmaj7]Hel[Gsus4]lo");
+    assert_eq!(rendered, "Cmaj7  Gsus4\nHel    lo\n");
+
+    // between words one column after it
+    let (song, rendered) = round_trip("Cmaj7 G\nHi    you");
+    assert_eq!(song, "[Cmaj7]Hi [G]you");
+    assert_eq!(rendered, "Cmaj7 G\nHi    you\n");
+
+    // a chord as wide as its syllable
+    let (song, _) = round_trip("Am  G\nHe  llo");
+    assert_eq!(song, "[Am]He[G]llo");
+
+    let (song, _) = round_trip("Cmaj7 Gsus4 D\nHi    you   there");
+    assert_eq!(song, "[Cmaj7]Hi [Gsus4]you [D]there");
+}
+
+#[test]
+fn render_wide_characters() {
+    let (song, rendered) = round_trip("Am      C\n日本語の歌詞");
+    assert_eq!(song, "[Am]日本語の[C]歌詞");
+    assert_eq!(rendered, "Am      C\n日本語の歌詞\n");
+}
+
+#[test]
+fn render_chords_without_lyrics() {
+    let (song, rendered) = round_trip("C G\n\nAm F\nC\nHello\n\nD");
+    assert_eq!(song, "[C] [G]\n\n[Am] [F]\n[C]Hello\n\n[D]");
+    assert_eq!(rendered, "C G\n\nAm F\nC\nHello\n\nD\n");
+
+    // the chords would be put above the line without chords
+    let (song, rendered) = round_trip("[C] [G]\nHello\nC   G\nla la");
+    assert_eq!(song, "[C] [G]\nHello\n[C]la l[G]a");
+    assert_eq!(rendered, "[C] [G]\nHello\nC   G\nla la\n");
+}
+
+#[test]
+fn round_trips() {
+    for text in [
+        "1. Hello darkness\nmy old friend\nR: la la",
+        "   Am      C\nR: Hello darkness",
+        "Intro: C G | Am F\n\nC       G\nI've come to talk",
+        "Am    F |  x2\nC  G/B  Am\nla la la la",
+        "Em  D  C  B7\nHe llo dar kness",
+        "     Am\nto the end",
+    ] {
+        round_trip(text);
+    }
+}
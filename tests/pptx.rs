@@ -0,0 +1,110 @@
+mod common;
+
+use std::io::Cursor;
+
+use spevnik::domain::{
+    core::LyricsWithChords,
+    pptx::{Pptx, PptxOptions},
+};
+use zip::ZipArchive;
+
+use common::{read, song};
+
+// the text of every paragraph of every slide
+fn slides(songs: &[LyricsWithChords], options: &PptxOptions) -> Vec<Vec<String>> {
+    let pptx = Pptx::render(songs, None, options).unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(pptx)).unwrap();
+
+    let presentation = read(&mut archive, "ppt/presentation.xml");
+    let count = presentation.matches("<p:sldId ").count();
+
+    let relationships = read(&mut archive, "ppt/_rels/presentation.xml.rels");
+    let content_types = read(&mut archive, "[Content_Types].xml");
+
+    (1..=count)
+        .map(|i| {
+            let name = format!("slides/slide{}.xml", i);
+            assert!(relationships.contains(&format!("Target=\"{}\"", name)));
+            assert!(content_types.contains(&format!("PartName=\"/ppt/{}\"", name)));
+
+            read(&mut archive, &format!("ppt/{}", name))
+                .split("<a:t>")
+                .skip(1)
+                .map(|t| t.split_once("</a:t>").unwrap().0.to_string())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn title_and_sections() {
+    let song = song(
+        "Song",
+        "Band & Co",
+        "[Am]Hello [C]darkness\nmy old friend\n\n{®:}\n[F]la la\n\n[C] [G]\n\n{®:}",
+    );
+
+    assert_eq!(
+        slides(&[song], &PptxOptions::default()),
+        vec![
+            vec!["Song", "Band &amp; Co"],
+            vec!["Hello darkness", "my old friend"],
+            vec!["la la"],
+            // the repeated chorus is projected again
+            vec!["la la"],
+        ]
+    );
+}
+
+#[test]
+fn long_sections() {
+    let lines: Vec<String> = (1..=7).map(|i| format!("line {}", i)).collect();
+    let text = lines.join("\n");
+    let options = PptxOptions {
+        lines_per_slide: 4,
+        ..Default::default()
+    };
+
+    // spread evenly, not four lines and three
+    assert_eq!(
+        slides(&[song("Song", "", &text)], &options),
+        vec![
+            vec!["Song"],
+            vec!["line 1", "line 2", "line 3", "line 4"],
+            vec!["line 5", "line 6", "line 7"],
+        ]
+    );
+
+    let lines: Vec<String> = (1..=9).map(|i| format!("line {}", i)).collect();
+    let text = lines.join("\n");
+    let sizes: Vec<usize> = slides(&[song("Song", "", &text)], &options)
+        .iter()
+        .skip(1)
+        .map(|s| s.len())
+        .collect();
+    assert_eq!(sizes, vec![3, 3, 3]);
+}
+
+#[test]
+fn font_sizes() {
+    let options = PptxOptions {
+        font_size: 30,
+        title_font_size: 60,
+        ..Default::default()
+    };
+
+    let pptx = Pptx::render(&[song("Song", "", "Text")], None, &options).unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(pptx)).unwrap();
+
+    assert!(read(&mut archive, "ppt/slides/slide1.xml").contains("sz=\"6000\""));
+    assert!(read(&mut archive, "ppt/slides/slide2.xml").contains("sz=\"3000\""));
+}
+
+#[test]
+fn many_songs() {
+    let songs = [song("First", "", "Text"), song("Second", "", "Text")];
+
+    let slides = slides(&songs, &PptxOptions::default());
+    assert_eq!(slides.len(), 4);
+    assert_eq!(slides[2], vec!["Second"]);
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use std::collections::HashMap;
+
+use scraper::Html;
+use spevnik::domain::{
+    core::{Options, SectionKind},
+    supermusic::Supermusic,
+};
+
+use common::{fixture, inline};
+
+fn from_html(name: &str) -> String {
+    let document = Html::parse_document(&fixture(name));
+
+    inline(&Supermusic::get_from_html(&document, None).unwrap())
+}
+
+#[test]
+fn html_fallback() {
+    let text = from_html("supermusic.html");
+    let lines: Vec<&str> = text.lines().collect();
+
+    // the newlines in the source are spaces, the <br> tags are the line breaks
+    assert_eq!(lines[0], "{1.} [Am]Hej, tam gdzieś z nad [E]czarnej wody");
+    assert_eq!(lines[1], "[E]Siada na koń kozak [Am]młody");
+    assert_eq!(lines[2], "");
+    assert_eq!(lines[3], "{®:} [C]Hej, hej, hej sokoły");
+    assert_eq!(lines[4], "Omijajcie   góry, lasy, doły");
+    assert_eq!(lines.len(), 5);
+}
+
+#[test]
+fn html_fallback_pre() {
+    assert_eq!(
+        from_html("supermusic_pre.html"),
+        "[Am]Hej, tam gdzieś z nad [E]czarnej wody\n\
+         [E]Siada na koń    kozak [Am]młody\n\
+         \n\
+         [C]Hej, hej, hej sokoły"
+    );
+}
+
+#[test]
+fn html_fallback_title() {
+    let document = Html::parse_document(&fixture("supermusic.html"));
+    let song = Supermusic::get_from_html(&document, None).unwrap();
+
+    assert_eq!(song.artist, "Ľudové");
+    assert_eq!(song.song_name, "Hej, sokoly");
+}
+
+#[test]
+fn metadata() {
+    let document = Html::parse_document(&fixture("supermusic.html"));
+    let metadata = Supermusic::get_from_html(&document, None).unwrap().metadata;
+
+    // the menu next to the song lists an album, a key and songs with "capo" in the name
+    assert_eq!(metadata.lyricist.as_deref(), Some("Maciej Kamieński"));
+    assert_eq!(metadata.composer.as_deref(), Some("ľudová"));
+    assert_eq!(metadata.album.as_deref(), Some("Poľské ľudové piesne"));
+    assert_eq!(metadata.key.as_deref(), Some("Am"));
+    assert_eq!(metadata.submitter.as_deref(), Some("spevak123"));
+    assert_eq!(metadata.capo, Some(2));
+}
+
+#[test]
+fn metadata_without_capo() {
+    let document = Html::parse_document(&fixture("supermusic_pre.html"));
+    let metadata = Supermusic::get_from_html(&document, None).unwrap().metadata;
+
+    assert_eq!(metadata.capo, None);
+    assert_eq!(metadata.album, None);
+}
+
+fn from_txt(txt: &str, options: Option<Options>) -> String {
+    let document = Html::parse_document(&fixture("supermusic_pre.html"));
+    let export = format!("Ľudové - Hej, sokoly\n\n{}", txt);
+
+    inline(&Supermusic::get(&document, export, options).unwrap())
+}
+
+#[test]
+fn section_markers() {
+    for (line, expected) in [
+        ("R: text", "{®:} text"),
+        ("R2: text", "{®:} text"),
+        ("r: text", "{®:} text"),
+        ("Ref.: text", "{®:} text"),
+        ("Ref: text", "{®:} text"),
+        ("Ref. text", "{®:} text"),
+        ("Refrén: text", "{®:} text"),
+        ("refren: text", "{®:} text"),
+        ("® text", "{®:} text"),
+        ("®: text", "{®:} text"),
+        ("®2 text", "{®:} text"),
+        ("1. text", "{1.} text"),
+        ("2) text", "{2)} text"),
+        ("3: text", "{3:} text"),
+        ("  1. text", "{1.} text"),
+        ("Bridge: text", "{Bridge:} text"),
+        ("Mezihra: text", "{Mezihra:} text"),
+        ("Predohra: text", "{Predohra:} text"),
+        ("Solo: text", "{Solo:} text"),
+        ("R:", "{®:}"),
+        ("1.", "{1.}"),
+    ] {
+        assert_eq!(from_txt(line, None), expected, "{}", line);
+    }
+}
+
+#[test]
+fn not_section_markers() {
+    for line in [
+        // a recitation, not a chorus
+        "Rec.: text",
+        "Ruka v ruke",
+        "R:text",
+        "1.5 litra",
+        "12:30 odchádza vlak",
+        "Poznámka: text",
+    ] {
+        assert_eq!(from_txt(line, None), line);
+    }
+}
+
+#[test]
+fn section_markers_before_chords() {
+    assert_eq!(
+        from_txt("1. [Am]Hej, tam gdzieś\nR: [C]Hej, hej", None),
+        "{1.} [Am]Hej, tam gdzieś\n{®:} [C]Hej, hej"
+    );
+}
+
+#[test]
+fn mapped_section_markers() {
+    let options = Options {
+        chorus_label: "Ref.".to_string(),
+        section_labels: HashMap::from([
+            (SectionKind::Verse, Some("Sloka {n}".to_string())),
+            (SectionKind::Bridge, None),
+        ]),
+    };
+
+    assert_eq!(
+        from_txt(
+            "1. text\ntext\nR: text\n2. text\nBridge: text",
+            Some(options)
+        ),
+        "{Sloka 1} text\ntext\n{Ref.} text\n{Sloka 2} text\ntext"
+    );
+}
+
+#[test]
+fn lyrics_ending_with_a_section_word() {
+    // the kinds are unmapped, a line read as a label would disappear
+    let options = Options {
+        section_labels: HashMap::from([
+            (SectionKind::Verse, None),
+            (SectionKind::Chorus, None),
+            (SectionKind::Bridge, None),
+        ]),
+        ..Options::default()
+    };
+
+    for line in [
+        "In the universe: stars",
+        "Verse of the night: text",
+        "Referent: text",
+        "Bridges: over the river",
+        "Zasloha: text",
+    ] {
+        assert_eq!(from_txt(line, Some(options.clone())), line);
+    }
+}
+
+#[test]
+fn single_chord_line() {
+    assert_eq!(from_txt("[Am]\nHej", None), "[Am]\nHej");
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use scraper::Html;
+use spevnik::domain::ultimate_guitar::UltimateGuitar;
+
+use common::{inline, ultimate_guitar_page};
+
+#[test]
+fn german_chord_names() {
+    let page = ultimate_guitar_page(
+        "Artist",
+        "Song",
+        "[ch]B[/ch] [ch]Bm[/ch] [ch]B7[/ch] [ch]Bb[/ch] [ch]Bbmaj7[/ch] [ch]B#[/ch] [ch]G/B[/ch] [ch]Bm/F#[/ch]",
+    );
+    let song = UltimateGuitar::get(&Html::parse_document(&page), None).unwrap();
+
+    assert_eq!(inline(&song), "[H] [Hm] [H7] [B] [Bmaj7] [C] [G/H] [Hm/F#]");
+}
+
+#[test]
+fn broken_song_data() {
+    let page = "<div class=\"js-store\" data-content=\"{&quot;store&quot;:\"></div>";
+
+    assert!(UltimateGuitar::get(&Html::parse_document(page), None).is_err());
+}
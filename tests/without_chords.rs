@@ -0,0 +1,58 @@
+mod common;
+
+use spevnik::domain::{chordpro::ChordPro, core::LyricsWithChords};
+
+use common::inline;
+
+fn without_chords(text: &str) -> String {
+    let song: LyricsWithChords = ChordPro::parse(text, None).unwrap().remove(0);
+
+    inline(&song.without_chords())
+}
+
+#[test]
+fn joins_the_words() {
+    assert_eq!(
+        without_chords("[Am]Hello dark[C]ness my [G] old friend [D]"),
+        "Hello darkness my old friend"
+    );
+    assert_eq!(without_chords("[F] la la"), "la la");
+}
+
+#[test]
+fn drops_chord_lines() {
+    assert_eq!(
+        without_chords("[C] [G]\n[Am]Hello\n| [Am] | [F] | x2\nfriend\n[E]"),
+        "Hello\nfriend"
+    );
+}
+
+#[test]
+fn keeps_sections_and_labels() {
+    assert_eq!(
+        without_chords(
+            "{c: Intro}\n[C] [G]\n\n{c: Verse 1}\n[Am]Hello\n\n{soc}\n[F]la\n{eoc}\n\n{chorus}"
+        ),
+        "{Intro}\n\n{Verse 1}\nHello\n\n{®:}\nla\n\n{®:}"
+    );
+}
+
+#[test]
+fn no_double_blank_lines() {
+    assert_eq!(
+        without_chords("Hello\n\n[C] [G]\n\nfriend\n\n[E]"),
+        "Hello\n\nfriend"
+    );
+}
+
+#[test]
+fn keeps_the_metadata() {
+    let song = ChordPro::parse("{title: Song}\n{artist: Band}\n{key: G}\n[G]la", None)
+        .unwrap()
+        .remove(0);
+    let without_chords = song.without_chords();
+
+    assert_eq!(without_chords.song_name, "Song");
+    assert_eq!(without_chords.artist, "Band");
+    assert_eq!(without_chords.metadata, song.metadata);
+}